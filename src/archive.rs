@@ -0,0 +1,403 @@
+//! Conversion between `SnowBinary` files and tar and zip archives.
+
+#[cfg(feature = "zip")]
+use std::io::{Seek, Write};
+use std::{
+    collections::BTreeSet,
+    io::{self, Read},
+};
+
+use crate::{
+    error::SnowBinError,
+    file::{SnowBinReader, SnowBinWriter},
+    format,
+    metadata::SnowBinMetadata,
+};
+
+#[cfg(feature = "zip")]
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// What was converted by an import or export between a `SnowBinary` file and a tar or zip
+/// archive.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct SnowBinArchiveReport {
+    entries: u64,
+    skipped: Vec<String>,
+}
+
+impl SnowBinArchiveReport {
+    /// Returns the number of entries that were converted.
+    #[must_use]
+    pub const fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Returns the paths or headers that could not be represented on the other side, in the order
+    /// they were found.
+    #[must_use]
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+}
+
+impl SnowBinWriter {
+    /// Writes every regular file in the tar `archive` as an entry, with its path as the header.
+    /// Paths are split by the file's separator, or `/` if it does not set one. If the file has
+    /// entry metadata, each file's modification time is kept.
+    ///
+    /// Directories are left out. Links, paths that are not UTF-8, and files that cannot be written
+    /// with the file info, such as paths longer than the header size, are skipped and reported.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinReader, SnowBinWriter};
+    ///
+    /// let mut archive = tar::Builder::new(Vec::new());
+    /// let mut header = tar::Header::new_gnu();
+    /// header.set_size(13);
+    /// archive
+    ///     .append_data(&mut header, "docs/readme.txt", &b"This is data!"[..])
+    ///     .unwrap();
+    /// let archive = archive.into_inner().unwrap();
+    ///
+    /// let info = SnowBinInfo::new(64, 64).unwrap();
+    /// let mut writer = SnowBinWriter::new(info, PathBuf::from("import.temp")).unwrap();
+    /// let report = writer.import_tar(&archive[..]).unwrap();
+    /// writer.close().unwrap();
+    /// assert_eq!(report.entries(), 1);
+    ///
+    /// let mut reader = SnowBinReader::new(PathBuf::from("import.temp")).unwrap();
+    /// assert_eq!(reader.read("docs/readme.txt").unwrap(), b"This is data!");
+    /// # std::fs::remove_file("import.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the archive is malformed or cannot be read from, or the file
+    /// cannot be written to.
+    #[cfg(feature = "tar")]
+    pub fn import_tar<R: Read>(
+        &mut self,
+        archive: R,
+    ) -> Result<SnowBinArchiveReport, SnowBinError> {
+        let mut report = SnowBinArchiveReport::default();
+        let mut archive = tar::Archive::new(archive);
+        for entry in archive.entries().map_err(|e| archive_error(None, e))? {
+            let mut entry = entry.map_err(|e| archive_error(None, e))?;
+            let kind = entry.header().entry_type();
+            if kind.is_dir() || kind.is_pax_global_extensions() {
+                continue;
+            }
+
+            let path = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+            let header = std::str::from_utf8(&entry.path_bytes())
+                .ok()
+                .and_then(|path| self.archive_header(path));
+            let Some(header) = header.filter(|_| kind.is_file())
+            else {
+                report.skipped.push(path);
+                continue;
+            };
+
+            let mut data = Vec::new();
+            entry
+                .read_to_end(&mut data)
+                .map_err(|e| archive_error(Some(&path), e))?;
+            let modified = entry.header().mtime().ok();
+            self.import(&mut report, &header, path, &data, modified)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Writes every file in the zip `archive` as an entry, with its path as the header. See
+    /// `import_tar`. Zip modification times have no time zone, and are read as UTC.
+    /// # Errors
+    /// Returns `SnowBinError` if the archive is malformed or cannot be read from, or the file
+    /// cannot be written to.
+    #[cfg(feature = "zip")]
+    pub fn import_zip<R: Read + Seek>(
+        &mut self,
+        archive: R,
+    ) -> Result<SnowBinArchiveReport, SnowBinError> {
+        let mut report = SnowBinArchiveReport::default();
+        let mut archive = zip::ZipArchive::new(archive).map_err(|e| archive_error(None, e))?;
+        for index in 0..archive.len() {
+            let mut file = archive
+                .by_index(index)
+                .map_err(|e| archive_error(None, e))?;
+            if file.is_dir() {
+                continue;
+            }
+
+            let name = file.name().ok().map(String::from);
+            let path = name
+                .clone()
+                .unwrap_or_else(|| String::from_utf8_lossy(file.name_raw()).into_owned());
+            // Symbolic links are stored as files holding their target.
+            let link = file
+                .unix_mode()
+                .is_some_and(|mode| mode & 0o170_000 == 0o120_000);
+            let header = name.and_then(|name| self.archive_header(&name));
+            let Some(header) = header.filter(|_| !link)
+            else {
+                report.skipped.push(path);
+                continue;
+            };
+
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)
+                .map_err(|e| archive_error(Some(&path), e))?;
+            let modified = file.last_modified().and_then(from_dos_time);
+            self.import(&mut report, &header, path, &data, modified)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Turns an archive path into a header, or `None` if it uses the file's separator as part of a
+    /// name.
+    fn archive_header(&self, path: &str) -> Option<String> {
+        let separator = format::separator(self.info());
+        let path = path.trim_start_matches("./");
+        if separator != '/' && path.contains(separator) {
+            return None;
+        }
+
+        Some(path.replace('/', separator.encode_utf8(&mut [0; 4])))
+    }
+
+    /// Writes an imported file, skipping it if it cannot be written with the file info.
+    fn import(
+        &mut self,
+        report: &mut SnowBinArchiveReport,
+        header: &str,
+        path: String,
+        data: &[u8],
+        modified: Option<u64>,
+    ) -> Result<(), SnowBinError> {
+        let metadata = match modified {
+            Some(modified) if self.info().entry_metadata() => {
+                SnowBinMetadata::new().with_modified(modified)
+            }
+            _ => SnowBinMetadata::new(),
+        };
+
+        match self.write_with_metadata(header, data, &metadata) {
+            Ok(()) => report.entries += 1,
+            Err(
+                SnowBinError::HeaderTooLong
+                | SnowBinError::InvalidPath
+                | SnowBinError::DataTooLong
+                | SnowBinError::DuplicateHeader,
+            ) => report.skipped.push(path),
+            Err(e) => return Err(e),
+        }
+
+        Ok(())
+    }
+}
+
+impl SnowBinReader {
+    /// Writes every entry as a regular file to the tar `archive`, with its header as the path.
+    /// Headers are split by the file's separator, or `/` if it does not set one. Modification times
+    /// are kept if the file has entry metadata.
+    ///
+    /// Headers that are not safe relative paths, such as ones with empty, `.` or `..` components
+    /// or backslashes, are skipped and reported, as are later entries of a header used more than
+    /// once.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinReader, SnowBinWriter};
+    ///
+    /// {
+    ///     let info = SnowBinInfo::new(16, 64).unwrap();
+    ///     let mut writer = SnowBinWriter::new(info, PathBuf::from("export.temp")).unwrap();
+    ///     writer.write("docs/readme", b"This is data!").unwrap();
+    ///     writer.write("../escape", b"Not exported").unwrap();
+    /// }
+    ///
+    /// let mut reader = SnowBinReader::new(PathBuf::from("export.temp")).unwrap();
+    /// let mut archive = Vec::new();
+    /// let report = reader.export_tar(&mut archive).unwrap();
+    /// assert_eq!(report.skipped(), ["../escape"]);
+    ///
+    /// let mut archive = tar::Archive::new(&archive[..]);
+    /// let entry = archive.entries().unwrap().next().unwrap().unwrap();
+    /// assert_eq!(entry.path().unwrap().to_str(), Some("docs/readme"));
+    /// # std::fs::remove_file("export.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, an entry exceeds the reader's limits,
+    /// or the archive cannot be written to.
+    #[cfg(feature = "tar")]
+    pub fn export_tar<W: io::Write>(
+        &mut self,
+        archive: W,
+    ) -> Result<SnowBinArchiveReport, SnowBinError> {
+        let mut builder = tar::Builder::new(archive);
+        let report = self.export(|path, data, modified| {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(modified.unwrap_or(0));
+            builder.append_data(&mut header, path, data)
+        })?;
+        builder.into_inner().map_err(|e| archive_error(None, e))?;
+
+        Ok(report)
+    }
+
+    /// Writes every entry as a deflated file to the zip `archive`, with its header as the path.
+    /// See `export_tar`. Modification times are written as UTC, and only kept between 1980 and
+    /// 2107. Entries of 4 GiB or more are written with Zip64 sizes.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, an entry exceeds the reader's limits,
+    /// or the archive cannot be written to.
+    #[cfg(feature = "zip")]
+    pub fn export_zip<W: Write + Seek>(
+        &mut self,
+        archive: W,
+    ) -> Result<SnowBinArchiveReport, SnowBinError> {
+        let mut writer = zip::ZipWriter::new(archive);
+        let report = self.export(|path, data, modified| {
+            let mut options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .large_file(data.len() as u64 >= u64::from(u32::MAX));
+            if let Some(modified) = modified.and_then(to_dos_time) {
+                options = options.last_modified_time(modified);
+            }
+            writer.start_file(path, options)?;
+            writer.write_all(data)
+        })?;
+        writer.finish().map_err(|e| archive_error(None, e))?;
+
+        Ok(report)
+    }
+
+    /// Passes the path, data, and modification time of every entry that can be represented in an
+    /// archive to `add`.
+    fn export(
+        &mut self,
+        mut add: impl FnMut(&str, &[u8], Option<u64>) -> io::Result<()>,
+    ) -> Result<SnowBinArchiveReport, SnowBinError> {
+        let info = self.info();
+        let separator = format::separator(info);
+
+        let mut report = SnowBinArchiveReport::default();
+        let mut seen = BTreeSet::new();
+        for entry in self.entries()? {
+            let header = String::from(entry.name(info));
+            let path = archive_path(&header, separator);
+            let Some(path) = path.filter(|_| seen.insert(header.clone()))
+            else {
+                report.skipped.push(header);
+                continue;
+            };
+
+            let data = self
+                .read_entry(&entry)
+                .map_err(|e| e.with_header(&header))?;
+            let modified = if info.entry_metadata() {
+                self.entry_metadata(&entry)
+                    .map_err(|e| e.with_header(&header))?
+                    .modified()
+            }
+            else {
+                None
+            };
+            add(&path, &data, modified).map_err(|e| archive_error(Some(&header), e))?;
+            report.entries += 1;
+        }
+
+        Ok(report)
+    }
+}
+
+/// Turns a header into a relative archive path, or `None` if it cannot be extracted safely.
+fn archive_path(header: &str, separator: char) -> Option<String> {
+    if separator != '/' && header.contains('/') {
+        return None;
+    }
+
+    let path = header.replace(separator, "/");
+    let safe = !path.contains(['\\', '\0'])
+        && path
+            .split('/')
+            .all(|component| !matches!(component, "" | "." | ".."));
+    safe.then_some(path)
+}
+
+fn archive_error(header: Option<&str>, source: impl Into<io::Error>) -> SnowBinError {
+    SnowBinError::ArchiveError {
+        header: header.map(String::from),
+        source: source.into(),
+    }
+}
+
+/// Unix seconds of a zip modification time, read as UTC.
+#[cfg(feature = "zip")]
+fn from_dos_time(time: zip::DateTime) -> Option<u64> {
+    if time.month() == 0 || time.day() == 0 {
+        return None;
+    }
+
+    let days = days_from_civil(
+        u64::from(time.year()),
+        u64::from(time.month()),
+        u64::from(time.day()),
+    );
+    Some(
+        days * SECONDS_PER_DAY
+            + u64::from(time.hour()) * 3600
+            + u64::from(time.minute()) * 60
+            + u64::from(time.second()),
+    )
+}
+
+/// Zip modification time of Unix seconds as UTC, or `None` if zip cannot store it.
+#[cfg(feature = "zip")]
+fn to_dos_time(seconds: u64) -> Option<zip::DateTime> {
+    let (year, month, day) = civil_from_days(seconds / SECONDS_PER_DAY);
+    let time = seconds % SECONDS_PER_DAY;
+    #[allow(clippy::cast_possible_truncation)]
+    zip::DateTime::from_date_and_time(
+        u16::try_from(year).ok()?,
+        month as u8,
+        day as u8,
+        (time / 3600) as u8,
+        (time / 60 % 60) as u8,
+        (time % 60) as u8,
+    )
+    .ok()
+}
+
+/// Days since 1970-01-01 of a date after it, using Howard Hinnant's `days_from_civil`.
+#[cfg(feature = "zip")]
+const fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month, and day of days since 1970-01-01, using Howard Hinnant's `civil_from_days`.
+#[cfg(feature = "zip")]
+const fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,140 @@
+use crate::{
+    error::SnowBinError, SnowBinInfo, DATA_SIZES, DEFAULT_DATA_SIZE, DEFAULT_HEADER_SIZE,
+    FLAG_DEDUP, FLAG_ENTRY_METADATA, FLAG_FILE_METADATA, FLAG_OUTBOARD, FLAG_VARIABLE_HEADERS,
+    FLAG_VARINT_SIZES,
+};
+
+/// Builds a `SnowBinInfo`, checking every option at once in `build`.
+///
+/// Default returns a `SnowBinInfoBuilder` with a header size of 8, a data size of 64, and no Spec 3
+/// options, which builds the same `SnowBinInfo` as `SnowBinInfo::default`.
+/// # Example
+/// ```
+/// use snowbinary::SnowBinInfo;
+///
+/// let info = SnowBinInfo::builder()
+///     .header_size(256)
+///     .variable_headers()
+///     .varint_sizes()
+///     .alignment(64)
+///     .separator('/')
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(info.header_size(), 256);
+/// assert_eq!(info.alignment(), 64);
+/// ```
+#[allow(clippy::module_name_repetitions)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SnowBinInfoBuilder {
+    header_size: u32,
+    data_size: u8,
+    flags: u32,
+    alignment: u32,
+    separator: Option<char>,
+}
+
+impl SnowBinInfoBuilder {
+    /// Creates a new default `SnowBinInfoBuilder`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            header_size: DEFAULT_HEADER_SIZE,
+            data_size: DATA_SIZES[DEFAULT_DATA_SIZE],
+            flags: 0,
+            alignment: 1,
+            separator: None,
+        }
+    }
+
+    /// Sets the max length of a header. See `SnowBinInfo::new`.
+    #[must_use]
+    pub const fn header_size(mut self, header_size: u32) -> Self {
+        self.header_size = header_size;
+        self
+    }
+
+    /// Sets the width of each entry's data length in bits. See `SnowBinInfo::new`.
+    #[must_use]
+    pub const fn data_size(mut self, data_size: u8) -> Self {
+        self.data_size = data_size;
+        self
+    }
+
+    /// See `SnowBinInfo::with_variable_headers`.
+    #[must_use]
+    pub const fn variable_headers(mut self) -> Self {
+        self.flags |= FLAG_VARIABLE_HEADERS;
+        self
+    }
+
+    /// See `SnowBinInfo::with_varint_sizes`.
+    #[must_use]
+    pub const fn varint_sizes(mut self) -> Self {
+        self.flags |= FLAG_VARINT_SIZES;
+        self
+    }
+
+    /// See `SnowBinInfo::with_entry_metadata`.
+    #[must_use]
+    pub const fn entry_metadata(mut self) -> Self {
+        self.flags |= FLAG_ENTRY_METADATA;
+        self
+    }
+
+    /// See `SnowBinInfo::with_file_metadata`.
+    #[must_use]
+    pub const fn file_metadata(mut self) -> Self {
+        self.flags |= FLAG_FILE_METADATA;
+        self
+    }
+
+    /// See `SnowBinInfo::with_outboard`.
+    #[must_use]
+    pub const fn outboard(mut self) -> Self {
+        self.flags |= FLAG_OUTBOARD;
+        self
+    }
+
+    /// See `SnowBinInfo::with_dedup`.
+    #[must_use]
+    pub const fn dedup(mut self) -> Self {
+        self.flags |= FLAG_DEDUP;
+        self
+    }
+
+    /// See `SnowBinInfo::with_alignment`.
+    #[must_use]
+    pub const fn alignment(mut self, alignment: u32) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// See `SnowBinInfo::with_separator`.
+    #[must_use]
+    pub const fn separator(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// Builds the `SnowBinInfo`.
+    /// # Errors
+    /// Returns `SnowBinError` if the header size is < 8, the data size is not 8, 16, 32, or 64, the
+    /// alignment is not a power of two up to 65536, or the separator is not ASCII punctuation.
+    pub fn build(self) -> Result<SnowBinInfo, SnowBinError> {
+        let mut info =
+            SnowBinInfo::new(self.header_size, self.data_size)?.with_alignment(self.alignment)?;
+        if let Some(separator) = self.separator {
+            info = info.with_separator(separator)?;
+        }
+        info.flags |= self.flags;
+
+        Ok(info)
+    }
+}
+
+impl Default for SnowBinInfoBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,357 @@
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::ops::Range;
+
+use crate::{
+    error::SnowBinError,
+    format::{self, Walker},
+    limits::SnowBinLimits,
+    reader::Input,
+    SnowBinInfo, HASH_SIZE,
+};
+
+/// How an entry differs between two files.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SnowBinChange {
+    /// The entry is only in the new file.
+    Added,
+    /// The entry is only in the old file.
+    Removed,
+    /// The entry is in both files with different data.
+    Changed,
+    /// The entry is in both files with the same data.
+    Unchanged,
+}
+
+/// Length and blake3 hash of the data of an entry.
+type Summary = (u64, [u8; HASH_SIZE as usize]);
+
+/// How one header differs between two files, part of a `SnowBinDiff`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SnowBinEntryDiff {
+    header: String,
+    change: SnowBinChange,
+    old: Option<Summary>,
+    new: Option<Summary>,
+}
+
+impl SnowBinEntryDiff {
+    /// Returns the header, without padding.
+    #[must_use]
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    /// Returns how the entry changed.
+    #[must_use]
+    pub const fn change(&self) -> SnowBinChange {
+        self.change
+    }
+
+    /// Returns the length of the data in the old file, if the entry is in it.
+    #[must_use]
+    pub fn old_size(&self) -> Option<u64> {
+        self.old.map(|(size, _)| size)
+    }
+
+    /// Returns the length of the data in the new file, if the entry is in it.
+    #[must_use]
+    pub fn new_size(&self) -> Option<u64> {
+        self.new.map(|(size, _)| size)
+    }
+
+    /// Returns the blake3 hash of the data in the old file, if the entry is in it.
+    #[must_use]
+    pub fn old_hash(&self) -> Option<[u8; 32]> {
+        self.old.map(|(_, hash)| hash)
+    }
+
+    /// Returns the blake3 hash of the data in the new file, if the entry is in it.
+    #[must_use]
+    pub fn new_hash(&self) -> Option<[u8; 32]> {
+        self.new.map(|(_, hash)| hash)
+    }
+
+    /// Returns how many bytes the data grew by, negative if it shrank. Missing entries count as
+    /// empty.
+    #[must_use]
+    pub fn size_delta(&self) -> i128 {
+        i128::from(self.new_size().unwrap_or(0)) - i128::from(self.old_size().unwrap_or(0))
+    }
+}
+
+/// A line of a text diff, returned by `SnowBinDiff::lines`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SnowBinDiffLine<'a> {
+    /// The line is in both texts.
+    Unchanged(&'a str),
+    /// The line is only in the old text.
+    Removed(&'a str),
+    /// The line is only in the new text.
+    Added(&'a str),
+}
+
+/// The differences between two `SnowBinary` files, entry by entry.
+///
+/// Entries are matched by header and compared by the blake3 hash of their data, so payloads are
+/// never held in memory. Files with outboard hashes already store these hashes, so their payloads
+/// are not even read. If a header is used more than once, only its first entry is compared, like
+/// `read` does. Entries are listed in the order of the old file, followed by added entries in the
+/// order of the new file.
+/// Returned by `SnowBinReader::diff` and `SnowBinDecoder::diff`.
+/// # Example
+/// ```
+/// use snowbinary::{SnowBinChange, SnowBinDecoder, SnowBinEncoder, SnowBinInfo};
+///
+/// let mut old = SnowBinEncoder::new(SnowBinInfo::default()).unwrap();
+/// old.write("README", b"Hello").unwrap();
+/// old.write("LOGO", b"old logo").unwrap();
+/// let old = old.finish().unwrap();
+///
+/// let mut new = SnowBinEncoder::new(SnowBinInfo::default()).unwrap();
+/// new.write("README", b"Hello").unwrap();
+/// new.write("LOGO", b"new, larger logo").unwrap();
+/// new.write("ICON", b"icon").unwrap();
+/// let new = new.finish().unwrap();
+///
+/// let old = SnowBinDecoder::new(&old).unwrap();
+/// let new = SnowBinDecoder::new(&new).unwrap();
+/// let diff = old.diff(&new).unwrap();
+///
+/// let changes = diff
+///     .changes()
+///     .map(|entry| (entry.header(), entry.change(), entry.size_delta()))
+///     .collect::<Vec<_>>();
+/// assert_eq!(
+///     changes,
+///     [
+///         ("LOGO", SnowBinChange::Changed, 8),
+///         ("ICON", SnowBinChange::Added, 4)
+///     ]
+/// );
+/// ```
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct SnowBinDiff {
+    entries: Vec<SnowBinEntryDiff>,
+}
+
+impl SnowBinDiff {
+    /// Returns every header of both files, including unchanged ones.
+    #[must_use]
+    pub fn entries(&self) -> &[SnowBinEntryDiff] {
+        &self.entries
+    }
+
+    /// Iterates over the headers that were added, removed, or changed.
+    pub fn changes(&self) -> impl Iterator<Item = &SnowBinEntryDiff> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.change != SnowBinChange::Unchanged)
+    }
+
+    /// Returns true if any header was added, removed, or changed.
+    #[must_use]
+    pub fn has_changes(&self) -> bool {
+        self.changes().next().is_some()
+    }
+
+    /// Compares two payloads byte by byte, returning the ranges of offsets where they differ.
+    /// Bytes past the end of the shorter payload are part of the last range.
+    /// # Example
+    /// ```
+    /// use snowbinary::SnowBinDiff;
+    ///
+    /// assert_eq!(SnowBinDiff::bytes(b"abcdef", b"abXdYfgh"), [2..3, 4..5, 6..8]);
+    /// ```
+    #[must_use]
+    pub fn bytes(old: &[u8], new: &[u8]) -> Vec<Range<u64>> {
+        let mut ranges: Vec<Range<u64>> = Vec::new();
+        for offset in 0..old.len().max(new.len()) {
+            if old.get(offset) == new.get(offset) {
+                continue;
+            }
+
+            let offset = offset as u64;
+            match ranges.last_mut() {
+                Some(range) if range.end == offset => range.end += 1,
+                _ => ranges.push(offset..offset + 1),
+            }
+        }
+
+        ranges
+    }
+
+    /// Compares two texts line by line, returning the shortest list of lines to remove from and
+    /// add to the old text to get the new one.
+    /// # Example
+    /// ```
+    /// use snowbinary::{SnowBinDiff, SnowBinDiffLine};
+    ///
+    /// assert_eq!(
+    ///     SnowBinDiff::lines("a\nb\nc", "a\nc\nd"),
+    ///     [
+    ///         SnowBinDiffLine::Unchanged("a"),
+    ///         SnowBinDiffLine::Removed("b"),
+    ///         SnowBinDiffLine::Unchanged("c"),
+    ///         SnowBinDiffLine::Added("d"),
+    ///     ]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn lines<'a>(old: &'a str, new: &'a str) -> Vec<SnowBinDiffLine<'a>> {
+        let old = old.lines().collect::<Vec<_>>();
+        let new = new.lines().collect::<Vec<_>>();
+        let trace = shortest_edit(&old, &new);
+
+        // Walk back from the end through the furthest reaching paths of each edit count.
+        let mut lines = Vec::new();
+        let (mut x, mut y) = (old.len(), new.len());
+        for edits in (1..trace.len()).rev() {
+            let (prev_x, prev_y) = previous_point(&trace[edits - 1], edits, x, y);
+            while x > prev_x && y > prev_y {
+                x -= 1;
+                y -= 1;
+                lines.push(SnowBinDiffLine::Unchanged(old[x]));
+            }
+            if x == prev_x {
+                y -= 1;
+                lines.push(SnowBinDiffLine::Added(new[y]));
+            }
+            else {
+                x -= 1;
+                lines.push(SnowBinDiffLine::Removed(old[x]));
+            }
+        }
+        while x > 0 {
+            x -= 1;
+            lines.push(SnowBinDiffLine::Unchanged(old[x]));
+        }
+
+        lines.reverse();
+        lines
+    }
+}
+
+/// Runs Myers' diff forward, returning for each edit count `d` the furthest `x` reached on each
+/// diagonal `k` in `-d..=d`, as `trace[d][k + d]`.
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn shortest_edit(old: &[&str], new: &[&str]) -> Vec<Vec<usize>> {
+    let (old_len, new_len) = (old.len() as isize, new.len() as isize);
+    let max = old_len + new_len;
+    let offset = max + 1;
+    let mut furthest = vec![0_usize; 2 * max as usize + 3];
+    let mut trace = Vec::new();
+
+    for d in 0..=max {
+        let mut done = false;
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d
+                || (k != d
+                    && furthest[(offset + k - 1) as usize] < furthest[(offset + k + 1) as usize]);
+            let mut x = if down {
+                furthest[(offset + k + 1) as usize] as isize
+            }
+            else {
+                furthest[(offset + k - 1) as usize] as isize + 1
+            };
+            let mut y = x - k;
+            while matches!(
+                (old.get(x as usize), new.get(y as usize)),
+                (Some(old_line), Some(new_line)) if old_line == new_line
+            ) {
+                x += 1;
+                y += 1;
+            }
+            furthest[(offset + k) as usize] = x as usize;
+            done |= x >= old_len && y >= new_len;
+        }
+
+        trace.push(furthest[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        if done {
+            break;
+        }
+    }
+
+    trace
+}
+
+/// The point that the path ending at `(x, y)` with `edits` edits came from, given the furthest
+/// points reached with one edit less.
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn previous_point(previous: &[usize], edits: usize, x: usize, y: usize) -> (usize, usize) {
+    let d = edits as isize;
+    let k = x as isize - y as isize;
+    // `previous` covers the diagonals -(d - 1)..=(d - 1).
+    let at = |k: isize| previous[(k + d - 1) as usize] as isize;
+
+    let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+    let prev_x = at(prev_k);
+    (prev_x as usize, (prev_x - prev_k) as usize)
+}
+
+/// Summarizes the entries of a file as their size and hash, keeping the first entry of each header.
+fn summarize<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+) -> Result<Vec<(String, Summary)>, SnowBinError> {
+    let mut seen = BTreeSet::new();
+    let mut entries = Vec::new();
+    let mut walker = Walker::new(input, info, limits)?;
+    while let Some(entry) = walker.next(input)? {
+        let name = String::from(entry.name(info));
+        if seen.insert(name.clone()) {
+            let hash = format::hash_entry(input, info, &entry).map_err(|e| e.with_header(&name))?;
+            entries.push((name, (entry.size, hash)));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Compares the entries of two files.
+pub fn diff<A: Input, B: Input>(
+    old: &mut A,
+    old_info: SnowBinInfo,
+    old_limits: SnowBinLimits,
+    new: &mut B,
+    new_info: SnowBinInfo,
+    new_limits: SnowBinLimits,
+) -> Result<SnowBinDiff, SnowBinError> {
+    let old = summarize(old, old_info, old_limits)?;
+    let new = summarize(new, new_info, new_limits)?;
+    let mut remaining = new.iter().cloned().collect::<BTreeMap<_, _>>();
+
+    let mut entries = Vec::new();
+    for (header, old) in old {
+        let new = remaining.remove(&header);
+        let change = match new {
+            None => SnowBinChange::Removed,
+            Some(new) if new == old => SnowBinChange::Unchanged,
+            Some(_) => SnowBinChange::Changed,
+        };
+        entries.push(SnowBinEntryDiff {
+            header,
+            change,
+            old: Some(old),
+            new,
+        });
+    }
+    for (header, new) in new {
+        if remaining.remove(&header).is_some() {
+            entries.push(SnowBinEntryDiff {
+                header,
+                change: SnowBinChange::Added,
+                old: None,
+                new: Some(new),
+            });
+        }
+    }
+
+    Ok(SnowBinDiff { entries })
+}
@@ -1,55 +1,212 @@
+use core::fmt;
+
+use crate::limits::SnowBinLimit;
+#[cfg(feature = "std")]
+use std::{
+    io,
+    path::PathBuf,
+    string::{String, ToString},
+};
+
 /// Types of errors that can occur when using `SnowBinInfo`, `SnowBinWriter`, and `SnowBinReader`.
 #[allow(clippy::module_name_repetitions)]
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum SnowBinError {
     /// The data size used is not 8, 16, 32, or 64.
     DataSizeNotAllowed,
+    /// The alignment used is not a power of two up to 65536.
+    AlignmentNotAllowed,
+    /// The path separator used is not ASCII punctuation.
+    SeparatorNotAllowed,
+    /// The header has an empty path component.
+    InvalidPath,
     /// The file given could not be created or opened.
-    CouldNotCreateOrOpenFile,
+    #[cfg(feature = "std")]
+    CouldNotCreateOrOpenFile {
+        /// Path of the file that was being created or opened.
+        path: PathBuf,
+        /// The underlying I/O error.
+        source: io::Error,
+    },
     /// Could not write to the file for some reason.
-    IOWriteError,
+    #[cfg(feature = "std")]
+    IOWriteError {
+        /// Byte offset in the file where the write started.
+        offset: u64,
+        /// Header of the entry being written, if any.
+        header: Option<String>,
+        /// The underlying I/O error.
+        source: io::Error,
+    },
+    /// A tar or zip archive could not be read or written.
+    #[cfg(feature = "std")]
+    ArchiveError {
+        /// Header of the entry being converted, if any.
+        header: Option<String>,
+        /// The underlying I/O or archive error.
+        source: io::Error,
+    },
     /// Header size given was < 8 bytes.
     HeaderSizeTooSmall,
     /// The header passed in was longer than the max header length allowed.
     HeaderTooLong,
+    /// A header was used by more than one entry while duplicates are rejected.
+    DuplicateHeader,
     /// Tried to write to file, but it was closed.
     IOWriterClosed,
+    /// Tried to write to file while a nested writer for one of its entries was not closed.
+    EntryNotFinished,
     /// Data length exceeded the max data length allowed.
     DataTooLong,
+    /// Tried to write metadata to a file that was not created with entry metadata.
+    MetadataNotEnabled,
     /// Could not read the file for some reason.
-    IOReadError,
-    /// File did not start with the "SNOW_BIN" header.
+    #[cfg(feature = "std")]
+    IOReadError {
+        /// Byte offset in the file where the read started.
+        offset: u64,
+        /// Header of the entry being read, if any.
+        header: Option<String>,
+        /// The underlying I/O error.
+        source: io::Error,
+    },
+    /// File did not start with the `SNOW_BIN` header.
     MalformedHeader,
-    /// Tried to get a UInt from the file and failed.
+    /// Tried to get a `UInt` from the file and failed.
     MalformedUInt,
+    /// An entry's metadata block had a key that was not UTF-8 or was repeated.
+    MalformedMetadata,
     /// Spec version of the file did not match the program Spec version.
     WrongSpecVersion,
     /// Reached the end of the file without finding the header.
     ReachedEOF,
     /// Verify hash does not match the data extracted.
     HashDoesNotMatch,
+    /// A deduplicated entry refers to data that is not before it in the file.
+    InvalidReference {
+        /// Byte offset in the file where the reference was read.
+        offset: u64,
+    },
+    /// The range requested from an entry ends after the entry does.
+    RangeOutOfBounds {
+        /// Length of the entry's data.
+        size: u64,
+    },
+    /// The input ended before a complete value could be read.
+    UnexpectedEof {
+        /// Byte offset where the read started.
+        offset: u64,
+        /// Number of bytes that were needed.
+        expected: u64,
+        /// Number of bytes that were available.
+        actual: u64,
+    },
+    /// The file uses Spec 3 options that this library does not know about.
+    UnsupportedFlags {
+        /// The flags stored in the file.
+        flags: u32,
+    },
+    /// A value read from the file exceeded one of the `SnowBinLimits`.
+    LimitExceeded {
+        /// The limit that was exceeded.
+        limit: SnowBinLimit,
+        /// The value read from the file.
+        value: u64,
+        /// The maximum allowed by the limit.
+        max: u64,
+    },
 }
-impl std::fmt::Display for SnowBinError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+
+impl SnowBinError {
+    /// Attaches `header` to I/O errors that do not have one yet.
+    #[cfg_attr(
+        not(feature = "std"),
+        allow(unused_mut, unused_variables, clippy::missing_const_for_fn)
+    )]
+    pub(crate) fn with_header(mut self, header: &str) -> Self {
+        #[cfg(feature = "std")]
+        if let Self::IOWriteError { header: h, .. }
+        | Self::IOReadError { header: h, .. }
+        | Self::ArchiveError { header: h, .. } = &mut self
+        {
+            if h.is_none() {
+                *h = Some(header.trim_end().to_string());
+            }
+        }
+        self
+    }
+}
+
+impl fmt::Display for SnowBinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::DataSizeNotAllowed => write!(f, "Data Size is not 8, 16, 32, or 64."),
-            Self::CouldNotCreateOrOpenFile => {
-                write!(f, "Could not create or open the file.")
+            Self::AlignmentNotAllowed => {
+                write!(f, "Alignment is not a power of two up to 65536.")
+            }
+            Self::SeparatorNotAllowed => write!(f, "Separator is not ASCII punctuation."),
+            Self::InvalidPath => write!(f, "Header has an empty path component."),
+            #[cfg(feature = "std")]
+            Self::CouldNotCreateOrOpenFile { path, source } => {
+                write!(
+                    f,
+                    "Could not create or open the file {}: {source}",
+                    path.display()
+                )
+            }
+            #[cfg(feature = "std")]
+            Self::IOWriteError {
+                offset,
+                header,
+                source,
+            } => {
+                write!(f, "Could not write to the file at offset {offset}")?;
+                if let Some(header) = header {
+                    write!(f, " (header \"{header}\")")?;
+                }
+                write!(f, ": {source}")
+            }
+            #[cfg(feature = "std")]
+            Self::ArchiveError { header, source } => {
+                write!(f, "Could not convert the archive")?;
+                if let Some(header) = header {
+                    write!(f, " (header \"{header}\")")?;
+                }
+                write!(f, ": {source}")
             }
-            Self::IOWriteError => write!(f, "Could not write to the file."),
             Self::HeaderSizeTooSmall => write!(f, "Header must be at least 8 bytes."),
             Self::HeaderTooLong => write!(f, "Header exceeds max header length."),
+            Self::DuplicateHeader => write!(f, "Header is used by more than one entry."),
             Self::IOWriterClosed => {
                 write!(f, "Could not write to the file because it was closed.")
             }
+            Self::EntryNotFinished => {
+                write!(f, "Could not write to the file because a nested entry was not finished.")
+            }
             Self::DataTooLong => write!(f, "Data exceeds max length."),
-            Self::IOReadError => write!(f, "Could not read from the file."),
+            Self::MetadataNotEnabled => {
+                write!(f, "Could not write metadata because the file does not store any.")
+            }
+            #[cfg(feature = "std")]
+            Self::IOReadError {
+                offset,
+                header,
+                source,
+            } => {
+                write!(f, "Could not read from the file at offset {offset}")?;
+                if let Some(header) = header {
+                    write!(f, " (header \"{header}\")")?;
+                }
+                write!(f, ": {source}")
+            }
             Self::MalformedHeader => {
                 write!(f, "File did not start with \"SNOW_BIN\" header.")
             }
             Self::MalformedUInt => {
                 write!(f, "Could not pull a uint from the file when expected.")
             }
+            Self::MalformedMetadata => write!(f, "Entry metadata is malformed."),
             Self::WrongSpecVersion => write!(f, "Spec version does not match."),
             Self::ReachedEOF => write!(
                 f,
@@ -58,7 +215,39 @@ impl std::fmt::Display for SnowBinError {
             Self::HashDoesNotMatch => {
                 write!(f, "Verification hash did not match data hash.")
             }
+            Self::InvalidReference { offset } => {
+                write!(f, "Entry at offset {offset} refers to data outside of the entries before it.")
+            }
+            Self::RangeOutOfBounds { size } => {
+                write!(f, "Range is outside of the entry's {size} bytes of data.")
+            }
+            Self::UnexpectedEof {
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Unexpected end of input at offset {offset}, expected {expected} bytes but only {actual} were available."
+            ),
+            Self::UnsupportedFlags { flags } => {
+                write!(f, "File uses unsupported options (flags {flags:#x}).")
+            }
+            Self::LimitExceeded { limit, value, max } => {
+                write!(f, "Limit on {limit} exceeded: {value} > {max}.")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SnowBinError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::CouldNotCreateOrOpenFile { source, .. }
+            | Self::IOWriteError { source, .. }
+            | Self::IOReadError { source, .. }
+            | Self::ArchiveError { source, .. } => Some(source),
+            _ => None,
         }
     }
 }
-impl std::error::Error for SnowBinError {}
@@ -0,0 +1,874 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, ErrorKind, Read},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    diff::{self, SnowBinDiff},
+    error::SnowBinError,
+    format::{self, Encoder, Entry, Walker},
+    limits::SnowBinLimits,
+    metadata::SnowBinMetadata,
+    nested::SnowBinNestedWriter,
+    options::{SnowBinDuplicates, SnowBinOpenOptions, SnowBinVerification},
+    outboard::{self, Outboard},
+    path,
+    reader::{self, Input, IoInput},
+    schema::{self, SnowBinSchema, SnowBinViolation},
+    summary::SnowBinWriteSummary,
+    writer::IoOutput,
+    SnowBinInfo,
+};
+
+/// What a `SnowBinWriter` does when it is dropped without being closed or finished.
+/// Errors are never raised on drop, use `SnowBinWriter::finish` to see them.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum SnowBinDropAction {
+    /// Writes the end header and hash, ignoring any errors. Nothing is written if closing was
+    /// already tried and failed.
+    #[default]
+    Close,
+    /// Leaves the file as it is, without an end header or hash, so it will not be read.
+    /// With atomic writes the temporary file is left instead.
+    Abort,
+    /// Removes the partially written file.
+    Remove,
+}
+
+/// Allows writing to a `SnowBinary` file.
+#[derive(Debug)]
+pub struct SnowBinWriter {
+    encoder: Encoder<IoOutput<File>>,
+    path: PathBuf,
+    /// Path actually written to with atomic writes, renamed to `path` once closed.
+    temp: Option<PathBuf>,
+    on_drop: SnowBinDropAction,
+    /// True once the file was closed and, with atomic writes, moved to `path`.
+    closed: bool,
+}
+
+impl SnowBinWriter {
+    /// Creates a new `SnowBinWriter` using the params of `SnowBinInfo`.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinWriter};
+    ///
+    /// let writer = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("file.temp"));
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be created or opened, or the file cannot be written to.
+    pub fn new(info: SnowBinInfo, path: PathBuf) -> Result<Self, SnowBinError> {
+        Self::with_metadata(info, path, &SnowBinMetadata::new())
+    }
+
+    /// Creates a new `SnowBinWriter` that stores `metadata` for the whole file, such as when and by
+    /// what it was written. Use `SnowBinMetadata::without_timestamps` for reproducible output.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinMetadata, SnowBinReader, SnowBinWriter};
+    ///
+    /// let info = SnowBinInfo::default().with_file_metadata();
+    /// let metadata = SnowBinMetadata::new()
+    ///     .with_application("packer", "1.2.0")
+    ///     .with_created_now();
+    /// {
+    ///     let mut writer =
+    ///         SnowBinWriter::with_metadata(info, PathBuf::from("provenance.temp"), &metadata)
+    ///             .unwrap();
+    ///     writer.write("Header", b"This is data!").unwrap();
+    /// }
+    ///
+    /// let reader = SnowBinReader::new(PathBuf::from("provenance.temp")).unwrap();
+    /// assert_eq!(reader.file_metadata().application(), Some("packer"));
+    /// # std::fs::remove_file("provenance.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if `new` would, or `info` was not created with
+    /// `SnowBinInfo::with_file_metadata`.
+    pub fn with_metadata(
+        info: SnowBinInfo,
+        path: PathBuf,
+        metadata: &SnowBinMetadata,
+    ) -> Result<Self, SnowBinError> {
+        SnowBinOpenOptions::new().create_with_metadata(info, path, metadata)
+    }
+
+    pub(crate) fn create(
+        info: SnowBinInfo,
+        path: PathBuf,
+        metadata: &SnowBinMetadata,
+        options: SnowBinOpenOptions,
+    ) -> Result<Self, SnowBinError> {
+        let temp = options.atomic.then(|| temp_path(&path));
+        let target = temp.as_ref().unwrap_or(&path);
+
+        // Opened for reading too, so nested entries can be hashed after they are written.
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(target)
+            .map_err(|source| SnowBinError::CouldNotCreateOrOpenFile {
+                path: target.clone(),
+                source,
+            })?;
+
+        let output = IoOutput::buffered(file, options.buffer_size);
+        let mut encoder = Encoder::new(info, output, metadata)?;
+        if options.duplicates == SnowBinDuplicates::Reject {
+            encoder.reject_duplicates();
+        }
+
+        Ok(Self {
+            encoder,
+            path,
+            temp,
+            on_drop: options.on_drop,
+            closed: false,
+        })
+    }
+
+    /// Sets what happens if the writer is dropped without being closed or finished, such as when
+    /// returning early because of an error. Defaults to `SnowBinDropAction::Close`.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinDropAction, SnowBinInfo, SnowBinWriter};
+    ///
+    /// {
+    ///     let mut writer = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("drop.temp"))
+    ///         .unwrap()
+    ///         .on_drop(SnowBinDropAction::Remove);
+    ///     writer.write("Header", b"This is data!").unwrap();
+    /// }
+    ///
+    /// assert!(!PathBuf::from("drop.temp").exists());
+    /// ```
+    #[must_use]
+    pub const fn on_drop(mut self, action: SnowBinDropAction) -> Self {
+        self.on_drop = action;
+        self
+    }
+
+    /// Returns the file info the file is written with.
+    #[must_use]
+    pub const fn info(&self) -> SnowBinInfo {
+        self.encoder.info()
+    }
+
+    /// Writes a header and some data to a `SnowBinary` file.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinError, SnowBinInfo, SnowBinWriter};
+    ///
+    /// let mut writer = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("file.temp"));
+    /// match &mut writer {
+    ///     Ok(writer) => {
+    ///         writer.write("Header", "This is data!".as_bytes()).unwrap();
+    ///     }
+    ///     Err(_) => {}
+    /// }
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the header is too long, the data is too long, the data size is not
+    /// allowed, the file could not be written to, or the header was already written and
+    /// duplicates are rejected, see `SnowBinOpenOptions::duplicates`.
+    pub fn write(&mut self, header: &str, data: &[u8]) -> Result<(), SnowBinError> {
+        self.encoder.write(header, data, &SnowBinMetadata::new())
+    }
+
+    /// Writes a header, some data, and attributes describing the data to a `SnowBinary` file.
+    /// The attributes can be read back with `SnowBinReader::metadata` without reading the data.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinMetadata, SnowBinReader, SnowBinWriter};
+    ///
+    /// let info = SnowBinInfo::default().with_entry_metadata();
+    /// {
+    ///     let mut writer = SnowBinWriter::new(info, PathBuf::from("metadata.temp")).unwrap();
+    ///     let metadata = SnowBinMetadata::new().with_content_type("text/plain");
+    ///     writer.write_with_metadata("Header", b"This is data!", &metadata).unwrap();
+    /// }
+    ///
+    /// let mut reader = SnowBinReader::new(PathBuf::from("metadata.temp")).unwrap();
+    /// let metadata = reader.metadata("Header").unwrap();
+    /// assert_eq!(metadata.content_type(), Some("text/plain"));
+    /// # std::fs::remove_file("metadata.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if `write` would, or the file was not created with
+    /// `SnowBinInfo::with_entry_metadata`.
+    pub fn write_with_metadata(
+        &mut self,
+        header: &str,
+        data: &[u8],
+        metadata: &SnowBinMetadata,
+    ) -> Result<(), SnowBinError> {
+        self.encoder.write(header, data, metadata)
+    }
+
+    /// Closes the writer. Dropping the writer also closes it by default, but ignores any errors.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinError, SnowBinInfo, SnowBinWriter};
+    ///
+    /// let mut writer = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("file.temp"));
+    /// match &mut writer {
+    ///     Ok(writer) => {
+    ///         writer.write("Header", "This is data!".as_bytes()).unwrap();
+    ///         writer.close().unwrap(); // Or let the writer drop.
+    ///     }
+    ///     Err(_) => {}
+    /// }
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be written to or the writer was already closed.
+    pub fn close(&mut self) -> Result<(), SnowBinError> {
+        self.finish_file().map(|_| ())
+    }
+
+    /// Closes the writer and returns a summary of what was written.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinWriter};
+    ///
+    /// let mut writer =
+    ///     SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("finish.temp")).unwrap();
+    /// writer.write("Header", b"This is data!").unwrap();
+    /// let summary = writer.finish().unwrap();
+    ///
+    /// assert_eq!(summary.entries(), 1);
+    /// assert_eq!(summary.size(), std::fs::metadata("finish.temp").unwrap().len());
+    /// # std::fs::remove_file("finish.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be written to or the writer was already closed.
+    /// The writer is then dropped as set by `on_drop`, without trying to close it again.
+    pub fn finish(mut self) -> Result<SnowBinWriteSummary, SnowBinError> {
+        self.finish_file()
+    }
+
+    /// Closes the encoder and, with atomic writes, moves the temporary file to the path.
+    fn finish_file(&mut self) -> Result<SnowBinWriteSummary, SnowBinError> {
+        let summary = self.encoder.close()?;
+
+        if let Some(temp) = &self.temp {
+            self.encoder.output_mut().error(|file| file.sync_all())?;
+            fs::rename(temp, &self.path)
+                .and_then(|()| sync_dir(&self.path))
+                .map_err(|source| SnowBinError::CouldNotCreateOrOpenFile {
+                    path: self.path.clone(),
+                    source,
+                })?;
+        }
+
+        self.closed = true;
+        Ok(summary)
+    }
+
+    /// Starts writing another `SnowBinary` file directly into an entry with `header`.
+    /// Entries written to the nested writer are streamed into this file, and the entry is finished
+    /// when the nested writer is closed. Read it back with `SnowBinReader::open_nested`.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinReader, SnowBinWriter};
+    ///
+    /// {
+    ///     let mut writer =
+    ///         SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("nested.temp")).unwrap();
+    ///     let mut plugin = writer.write_nested("Plugin", SnowBinInfo::default()).unwrap();
+    ///     plugin.write("Code", b"print()").unwrap();
+    ///     plugin.close().unwrap();
+    /// }
+    ///
+    /// let mut reader = SnowBinReader::new(PathBuf::from("nested.temp")).unwrap();
+    /// let mut plugin = reader.open_nested("Plugin").unwrap();
+    /// assert_eq!(plugin.read("Code").unwrap(), b"print()");
+    /// # std::fs::remove_file("nested.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the header is too long, the file could not be written to, or the
+    /// writer was closed.
+    pub fn write_nested(
+        &mut self,
+        header: &str,
+        info: SnowBinInfo,
+    ) -> Result<SnowBinNestedWriter<'_>, SnowBinError> {
+        SnowBinNestedWriter::new(&mut self.encoder, header, info)
+    }
+}
+
+impl Drop for SnowBinWriter {
+    fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
+
+        match self.on_drop {
+            // The encoder is done if closing already failed partway.
+            SnowBinDropAction::Close if !self.encoder.is_done() => {
+                let _ = self.close();
+            }
+            SnowBinDropAction::Close | SnowBinDropAction::Abort => (),
+            SnowBinDropAction::Remove => {
+                let _ = fs::remove_file(self.temp.as_ref().unwrap_or(&self.path));
+            }
+        }
+    }
+}
+
+/// Path of the temporary file that an atomic write to `path` goes to, next to `path` so that it
+/// can be renamed over it. Every writer of the process gets its own.
+fn temp_path(path: &Path) -> PathBuf {
+    static WRITERS: AtomicU64 = AtomicU64::new(0);
+
+    let writer = WRITERS.fetch_add(1, Ordering::Relaxed);
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}.{writer}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+/// Syncs the directory of `path`, so that a file created or renamed there survives a crash.
+/// Only Unix needs this, elsewhere it does nothing.
+pub fn sync_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+/// Allows reading from a `SnowBinary` file.
+#[derive(Debug)]
+pub struct SnowBinReader {
+    path: PathBuf,
+    info: SnowBinInfo,
+    options: SnowBinOpenOptions,
+    metadata: SnowBinMetadata,
+    input: IoInput<BufReader<File>>,
+}
+
+impl SnowBinReader {
+    /// Creates a new `SnowBinReader`. Params are pulled from the file info.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinReader};
+    ///
+    /// let reader = SnowBinReader::new(PathBuf::from("file.temp")).unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be created or opened, or the file cannot be read from.
+    pub fn new(path: PathBuf) -> Result<Self, SnowBinError> {
+        SnowBinOpenOptions::new().open(path)
+    }
+
+    /// Creates a new `SnowBinReader` that enforces `limits` while reading.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinLimits, SnowBinReader};
+    ///
+    /// let limits = SnowBinLimits::new().max_entry_size(1024);
+    /// let reader = SnowBinReader::with_limits(PathBuf::from("file.temp"), limits);
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be opened, the file cannot be read from, or the
+    /// file exceeds `limits`.
+    pub fn with_limits(path: PathBuf, limits: SnowBinLimits) -> Result<Self, SnowBinError> {
+        SnowBinOpenOptions::new().limits(limits).open(path)
+    }
+
+    /// Creates a new `SnowBinReader` without hashing the whole file first, so opening a large file
+    /// is instant. If the file was written with `SnowBinInfo::with_outboard`, entries are checked
+    /// as they are read and only the parts that are read are hashed. Otherwise nothing is checked.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinLimits, SnowBinReader, SnowBinWriter};
+    ///
+    /// {
+    ///     let info = SnowBinInfo::default().with_outboard();
+    ///     let mut writer = SnowBinWriter::new(info, PathBuf::from("outboard.temp")).unwrap();
+    ///     writer.write("Header", &[7_u8; 100_000]).unwrap();
+    /// }
+    ///
+    /// let path = PathBuf::from("outboard.temp");
+    /// let mut reader = SnowBinReader::unverified(path, SnowBinLimits::default()).unwrap();
+    /// assert_eq!(reader.read_range("Header", 50_000..50_010).unwrap(), [7_u8; 10]);
+    /// # std::fs::remove_file("outboard.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be opened, the file cannot be read from, or the
+    /// file info exceeds `limits`.
+    pub fn unverified(path: PathBuf, limits: SnowBinLimits) -> Result<Self, SnowBinError> {
+        SnowBinOpenOptions::new()
+            .verification(SnowBinVerification::Skip)
+            .limits(limits)
+            .open(path)
+    }
+
+    pub(crate) fn open_with(
+        path: PathBuf,
+        options: SnowBinOpenOptions,
+    ) -> Result<Self, SnowBinError> {
+        let file = Self::open(&path)?;
+        let input = IoInput::new(BufReader::with_capacity(options.buffer_size, file));
+        Self::from_input(path, input, options)
+    }
+
+    fn open(path: &PathBuf) -> Result<File, SnowBinError> {
+        File::open(path).map_err(|source| SnowBinError::CouldNotCreateOrOpenFile {
+            path: path.clone(),
+            source,
+        })
+    }
+
+    fn from_input(
+        path: PathBuf,
+        mut input: IoInput<BufReader<File>>,
+        options: SnowBinOpenOptions,
+    ) -> Result<Self, SnowBinError> {
+        let limits = options.limits;
+        if options.verification == SnowBinVerification::Full {
+            Self::verify(&mut input, limits)?;
+        }
+        let info = format::read_info(&mut input, limits)?;
+        let metadata = format::read_file_metadata(&mut input, info, limits)?;
+        if options.duplicates == SnowBinDuplicates::Reject {
+            format::check_duplicates(&mut input, info, limits)?;
+        }
+
+        Ok(Self {
+            path,
+            info,
+            options,
+            metadata,
+            input,
+        })
+    }
+
+    fn verify(
+        input: &mut IoInput<BufReader<File>>,
+        limits: SnowBinLimits,
+    ) -> Result<(), SnowBinError> {
+        #[cfg(feature = "parallel")]
+        {
+            let len = input.len()?;
+            if let Some(map) = map(input.inner().get_ref(), input.absolute(0), len)? {
+                return format::verify_slice(&map, limits);
+            }
+        }
+
+        format::verify(input, limits)
+    }
+
+    /// Returns the file info the file was written with.
+    #[must_use]
+    pub const fn info(&self) -> SnowBinInfo {
+        self.info
+    }
+
+    /// Returns the metadata stored for the whole file.
+    /// Files without file metadata have empty metadata.
+    #[must_use]
+    pub const fn file_metadata(&self) -> &SnowBinMetadata {
+        &self.metadata
+    }
+
+    /// Reads data from the file using the header.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinError, SnowBinInfo, SnowBinReader};
+    ///
+    /// let mut reader = SnowBinReader::new(PathBuf::from("file.temp"));
+    /// match &mut reader {
+    ///     Ok(reader) => {
+    ///         let data = reader.read("Header"); // May return error
+    ///     }
+    ///     Err(_) => {}
+    /// }
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, the end of the file was reached, or
+    /// an entry exceeds the reader's limits.
+    pub fn read(&mut self, header: &str) -> Result<Vec<u8>, SnowBinError> {
+        let entry = format::find_entry(&mut self.input, self.info, self.options.limits, header)?;
+        self.read_entry(&entry).map_err(|e| e.with_header(header))
+    }
+
+    /// Reads the data of `entry`, checking it against its outboard hashes if the whole file was
+    /// not verified.
+    pub(crate) fn read_entry(&mut self, entry: &Entry) -> Result<Vec<u8>, SnowBinError> {
+        if self.options.verification == SnowBinVerification::Skip && self.info.outboard() {
+            let outboard = Outboard::read(&mut self.input, entry)?;
+            return outboard::read_range(&mut self.input, entry, Some(&outboard), 0, entry.size);
+        }
+
+        self.input.seek(entry.offset)?;
+        reader::read_bytes(&mut self.input, entry.size)
+    }
+
+    /// Reads the metadata of `entry`.
+    pub(crate) fn entry_metadata(
+        &mut self,
+        entry: &Entry,
+    ) -> Result<SnowBinMetadata, SnowBinError> {
+        format::entry_metadata(&mut self.input, entry)
+    }
+
+    /// Lists every entry in the file, in the order they were written.
+    pub(crate) fn entries(&mut self) -> Result<Vec<Entry>, SnowBinError> {
+        let mut entries = Vec::new();
+        let mut walker = Walker::new(&mut self.input, self.info, self.options.limits)?;
+        while let Some(entry) = walker.next(&mut self.input)? {
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+
+    /// Reads `range` of the data of the entry with `header`.
+    /// If the file has outboard hashes, only the 16 KiB groups that `range` touches are read and
+    /// checked, failing with `HashDoesNotMatch` if any of them was corrupted.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, the end of the file was reached,
+    /// `range` is outside of the entry, or the data does not match its outboard hashes.
+    pub fn read_range(&mut self, header: &str, range: Range<u64>) -> Result<Vec<u8>, SnowBinError> {
+        let (entry, outboard) = self.entry(header)?;
+        outboard::read_range(
+            &mut self.input,
+            &entry,
+            outboard.as_ref(),
+            range.start,
+            range.end,
+        )
+        .map_err(|e| e.with_header(header))
+    }
+
+    /// Streams the data of the entry with `header` through `Read`, without loading all of it.
+    /// If the file has outboard hashes, each 16 KiB group is checked as it is read, and reading
+    /// fails with `ErrorKind::InvalidData` at the first group that was corrupted.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, the end of the file was reached, or
+    /// the outboard hashes are corrupted.
+    pub fn stream(&mut self, header: &str) -> Result<SnowBinEntryReader<'_>, SnowBinError> {
+        let (entry, outboard) = self.entry(header)?;
+
+        Ok(SnowBinEntryReader {
+            input: &mut self.input,
+            entry,
+            outboard,
+            position: 0,
+            group: Vec::new(),
+            group_start: 0,
+        })
+    }
+
+    fn entry(&mut self, header: &str) -> Result<(Entry, Option<Outboard>), SnowBinError> {
+        let entry = format::find_entry(&mut self.input, self.info, self.options.limits, header)?;
+        let outboard = if self.info.outboard() {
+            Some(Outboard::read(&mut self.input, &entry).map_err(|e| e.with_header(header))?)
+        }
+        else {
+            None
+        };
+
+        Ok((entry, outboard))
+    }
+
+    /// Reads the metadata of an entry without reading its data.
+    /// Entries of files without entry metadata have empty metadata.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, the end of the file was reached, the
+    /// metadata is malformed, or an entry exceeds the reader's limits.
+    pub fn metadata(&mut self, header: &str) -> Result<SnowBinMetadata, SnowBinError> {
+        format::metadata(&mut self.input, self.info, self.options.limits, header)
+    }
+
+    /// Opens the entry with `header` as a `SnowBinary` file of its own, such as one written with
+    /// `SnowBinWriter::write_nested`. The file is opened again and only the entry's bytes are read,
+    /// so nothing is extracted. The nested reader uses the same options as this one.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, the end of the file was reached, or
+    /// the entry is not a valid `SnowBinary` file.
+    pub fn open_nested(&mut self, header: &str) -> Result<Self, SnowBinError> {
+        let size = format::find(&mut self.input, self.info, self.options.limits, header)?;
+        let base = self.input.absolute(self.input.position());
+
+        let file = BufReader::with_capacity(self.options.buffer_size, Self::open(&self.path)?);
+        let input = IoInput::window(file, base, size)?;
+        Self::from_input(self.path.clone(), input, self.options)
+    }
+
+    /// Lists every header in the file, in the order they were written.
+    /// Padding is removed from fixed size headers.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from or an entry exceeds the reader's limits.
+    pub fn headers(&mut self) -> Result<Vec<String>, SnowBinError> {
+        format::headers(&mut self.input, self.info, self.options.limits)
+    }
+
+    /// Lists the paths of the direct children of `prefix`, in the order they first appear.
+    /// Paths are split by the file's separator, or `/` if it does not set one, and an empty prefix
+    /// lists the top level.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinInfo, SnowBinReader, SnowBinWriter};
+    ///
+    /// let info = SnowBinInfo::new(64, 64).unwrap().with_separator('/').unwrap();
+    /// {
+    ///     let mut writer = SnowBinWriter::new(info, PathBuf::from("children.temp")).unwrap();
+    ///     writer.write("textures/ui/button", b"button").unwrap();
+    ///     writer.write("textures/ui/panel", b"panel").unwrap();
+    ///     writer.write("sounds/ui/click", b"click").unwrap();
+    /// }
+    ///
+    /// let mut reader = SnowBinReader::new(PathBuf::from("children.temp")).unwrap();
+    /// assert_eq!(reader.children("").unwrap(), ["textures", "sounds"]);
+    /// assert_eq!(
+    ///     reader.children("textures/ui").unwrap(),
+    ///     ["textures/ui/button", "textures/ui/panel"]
+    /// );
+    /// # std::fs::remove_file("children.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from or an entry exceeds the reader's limits.
+    pub fn children(&mut self, prefix: &str) -> Result<Vec<String>, SnowBinError> {
+        format::children(&mut self.input, self.info, self.options.limits, prefix)
+    }
+
+    /// Compares the entries of this file, as the old one, with those of `new`. See `SnowBinDiff`.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{SnowBinChange, SnowBinInfo, SnowBinReader, SnowBinWriter};
+    ///
+    /// for (path, data) in [("v1.temp", b"old"), ("v2.temp", b"new")] {
+    ///     let mut writer = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from(path)).unwrap();
+    ///     writer.write("Header", data).unwrap();
+    /// }
+    ///
+    /// let mut old = SnowBinReader::new(PathBuf::from("v1.temp")).unwrap();
+    /// let mut new = SnowBinReader::new(PathBuf::from("v2.temp")).unwrap();
+    /// let diff = old.diff(&mut new).unwrap();
+    /// assert_eq!(diff.entries()[0].change(), SnowBinChange::Changed);
+    /// # std::fs::remove_file("v1.temp").unwrap();
+    /// # std::fs::remove_file("v2.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if either file cannot be read from or an entry exceeds its reader's
+    /// limits.
+    pub fn diff(&mut self, new: &mut Self) -> Result<SnowBinDiff, SnowBinError> {
+        diff::diff(
+            &mut self.input,
+            self.info,
+            self.options.limits,
+            &mut new.input,
+            new.info,
+            new.options.limits,
+        )
+    }
+
+    /// Checks every entry against `schema`, returning every violation instead of failing on the
+    /// first one. Only the data of entries whose type needs it is read. See `SnowBinSchema`.
+    /// # Example
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use snowbinary::{
+    ///     SnowBinField, SnowBinInfo, SnowBinReader, SnowBinSchema, SnowBinValueType, SnowBinWriter,
+    /// };
+    ///
+    /// let schema = SnowBinSchema::new()
+    ///     .required("title", SnowBinField::new(SnowBinValueType::Utf8))
+    ///     .required("count", SnowBinField::new(SnowBinValueType::U64));
+    /// {
+    ///     let mut writer = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("schema.temp"))
+    ///         .unwrap();
+    ///     writer.write("title", b"Snow").unwrap();
+    /// }
+    ///
+    /// let mut reader = SnowBinReader::new(PathBuf::from("schema.temp")).unwrap();
+    /// let violations = reader.validate(&schema).unwrap();
+    /// assert_eq!(violations[0].to_string(), "Required header \"count\" is missing.");
+    /// # std::fs::remove_file("schema.temp").unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from or an entry exceeds the limits.
+    pub fn validate(
+        &mut self,
+        schema: &SnowBinSchema,
+    ) -> Result<Vec<SnowBinViolation>, SnowBinError> {
+        schema::validate(&mut self.input, self.info, self.options.limits, schema)
+    }
+
+    /// Iterates over the headers and data of `prefix` and every entry nested under it.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from.
+    pub fn subtree(&mut self, prefix: &str) -> Result<SnowBinSubtree<'_>, SnowBinError> {
+        let walker = Walker::new(&mut self.input, self.info, self.options.limits)?;
+
+        Ok(SnowBinSubtree {
+            reader: self,
+            prefix: String::from(prefix),
+            walker: Some(walker),
+        })
+    }
+}
+
+/// Maps `len` bytes of `file` at `base` into memory, so they can be hashed on several threads.
+/// Returns `None` if there is nothing to map or the bytes do not fit in memory.
+#[cfg(feature = "parallel")]
+pub fn map(file: &File, base: u64, len: u64) -> Result<Option<memmap2::Mmap>, SnowBinError> {
+    let Ok(len) = usize::try_from(len)
+    else {
+        return Ok(None);
+    };
+    if len == 0 {
+        return Ok(None);
+    }
+
+    // SAFETY: The map is only read from while it is alive. The file being changed by another
+    // process at the same time makes the hash not match, as it would for any other read.
+    let map = unsafe { memmap2::MmapOptions::new().offset(base).len(len).map(file) }.map_err(
+        |source| SnowBinError::IOReadError {
+            offset: 0,
+            header: None,
+            source,
+        },
+    )?;
+
+    Ok(Some(map))
+}
+
+/// Iterator over the entries under a path of a `SnowBinReader`, returned by `SnowBinReader::subtree`.
+#[derive(Debug)]
+pub struct SnowBinSubtree<'a> {
+    reader: &'a mut SnowBinReader,
+    prefix: String,
+    walker: Option<Walker>,
+}
+
+impl Iterator for SnowBinSubtree<'_> {
+    type Item = Result<(String, Vec<u8>), SnowBinError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let info = self.reader.info;
+        let separator = format::separator(info);
+
+        loop {
+            let entry = match self.walker.as_mut()?.next(&mut self.reader.input) {
+                Ok(Some(entry)) => entry,
+                Ok(None) => {
+                    self.walker = None;
+                    return None;
+                }
+                Err(e) => {
+                    self.walker = None;
+                    return Some(Err(e));
+                }
+            };
+
+            let name = entry.name(info);
+            if path::in_subtree(name, &self.prefix, separator) {
+                let data = self
+                    .reader
+                    .read_entry(&entry)
+                    .map_err(|e| e.with_header(name));
+                return Some(data.map(|data| (String::from(name), data)));
+            }
+        }
+    }
+}
+
+/// Reads the data of an entry of a `SnowBinReader`, returned by `SnowBinReader::stream`.
+#[derive(Debug)]
+pub struct SnowBinEntryReader<'a> {
+    input: &'a mut IoInput<BufReader<File>>,
+    entry: Entry,
+    outboard: Option<Outboard>,
+    position: u64,
+    group: Vec<u8>,
+    group_start: u64,
+}
+
+impl Read for SnowBinEntryReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.entry.size - self.position;
+        if remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let Some(outboard) = &self.outboard
+        else {
+            #[allow(clippy::cast_possible_truncation)]
+            let len = remaining.min(buf.len() as u64) as usize;
+            self.input
+                .seek(self.entry.offset + self.position)
+                .and_then(|()| self.input.read(&mut buf[..len]))
+                .map_err(into_io)?;
+            self.position += len as u64;
+            return Ok(len);
+        };
+
+        let group_end = self.group_start + self.group.len() as u64;
+        if self.position < self.group_start || self.position >= group_end {
+            let index = self.position / outboard::GROUP_LEN;
+            self.group =
+                outboard::read_group(self.input, &self.entry, outboard, index).map_err(into_io)?;
+            self.group_start = index * outboard::GROUP_LEN;
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let from = (self.position - self.group_start) as usize;
+        let len = (self.group.len() - from).min(buf.len());
+        buf[..len].copy_from_slice(&self.group[from..from + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+fn into_io(error: SnowBinError) -> io::Error {
+    match error {
+        SnowBinError::IOReadError { source, .. } => source,
+        SnowBinError::UnexpectedEof { .. } => io::Error::new(ErrorKind::UnexpectedEof, error),
+        error => io::Error::new(ErrorKind::InvalidData, error),
+    }
+}
@@ -0,0 +1,1110 @@
+//! Format logic shared by the in-memory and file based readers and writers.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::ops::Range;
+
+use crate::{
+    error::SnowBinError,
+    limits::{SnowBinLimit, SnowBinLimits},
+    metadata::SnowBinMetadata,
+    outboard::{self, OutboardHasher},
+    path,
+    reader::{self, Input},
+    summary::SnowBinWriteSummary,
+    writer::{self, Output, Patch},
+    SnowBinInfo, HASH_SIZE, KNOWN_FLAGS, MAX_ALIGNMENT, MIN_VERSION_SPEC, VERSION_SPEC,
+};
+
+const HASH_CHUNK: usize = 64 * 1024;
+/// Buffer size used to verify inputs that are not in memory. Larger with `parallel`, so that each
+/// buffer is worth splitting across threads.
+const VERIFY_CHUNK: usize = if cfg!(feature = "parallel") { 16 * 1024 * 1024 } else { HASH_CHUNK };
+/// Smallest update that is hashed on several threads with `parallel`.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN: usize = 128 * 1024;
+/// Header length that marks the end of the entries when headers are length prefixed.
+const END_MARKER: u32 = u32::MAX;
+
+/// Writes the `SnowBinary` framing to an `Output`, hashing everything as it goes.
+#[derive(Debug)]
+pub struct Encoder<O> {
+    info: SnowBinInfo,
+    output: O,
+    hasher: blake3::Hasher,
+    offset: u64,
+    pending: Option<Pending>,
+    entries: u64,
+    /// Encoded headers written so far, if duplicates are rejected.
+    headers: Option<BTreeSet<Vec<u8>>>,
+    /// Offsets of the payloads written so far by their hash, if identical payloads are stored once.
+    payloads: BTreeMap<[u8; HASH_SIZE as usize], u64>,
+    done: bool,
+}
+
+/// An entry whose data is being streamed in by a nested writer.
+#[derive(Clone, Debug)]
+struct Pending {
+    /// Offset of the header, and the hash to restore if the entry is discarded.
+    start: u64,
+    hasher: blake3::Hasher,
+    header: Vec<u8>,
+    length_offset: u64,
+    data_start: u64,
+}
+
+impl<O: Output> Encoder<O> {
+    /// Creates a new `Encoder` and writes the file info and file metadata to `output`.
+    pub fn new(
+        info: SnowBinInfo,
+        output: O,
+        metadata: &SnowBinMetadata,
+    ) -> Result<Self, SnowBinError> {
+        let metadata = encode_file_metadata(info, metadata)?;
+        let mut encoder = Self::unstarted(info, output);
+        encoder.start(&metadata)?;
+        Ok(encoder)
+    }
+
+    /// Creates a new `Encoder` without writing anything to `output`, see `start`.
+    pub fn unstarted(info: SnowBinInfo, output: O) -> Self {
+        Self {
+            info,
+            output,
+            hasher: blake3::Hasher::new(),
+            offset: 0,
+            pending: None,
+            entries: 0,
+            headers: None,
+            payloads: BTreeMap::new(),
+            done: false,
+        }
+    }
+
+    /// Writes the file info and the file metadata encoded by `encode_file_metadata`.
+    pub fn start(&mut self, metadata: &[u8]) -> Result<(), SnowBinError> {
+        self.put(b"SNOW_BIN")?;
+        self.put(&self.info.version().to_le_bytes())?;
+        self.put(&self.info.header_size.to_le_bytes())?;
+        self.put(&self.info.data_size.to_le_bytes())?;
+        if self.info.version() >= 3 {
+            self.put(&self.info.flags.to_le_bytes())?;
+        }
+        self.put(metadata)
+    }
+
+    /// Writes and hashes `data`.
+    fn put(&mut self, data: &[u8]) -> Result<(), SnowBinError> {
+        self.raw(data)?;
+        update(&mut self.hasher, data);
+        Ok(())
+    }
+
+    /// Writes `data` without hashing it.
+    fn raw(&mut self, data: &[u8]) -> Result<(), SnowBinError> {
+        self.output.write(data)?;
+        self.offset += data.len() as u64;
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub const fn info(&self) -> SnowBinInfo {
+        self.info
+    }
+
+    pub const fn output_mut(&mut self) -> &mut O {
+        &mut self.output
+    }
+
+    pub fn into_output(self) -> O {
+        self.output
+    }
+
+    pub const fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Makes writing a header that was already written fail with `DuplicateHeader`.
+    #[cfg(feature = "std")]
+    pub fn reject_duplicates(&mut self) {
+        self.headers.get_or_insert_with(BTreeSet::new);
+    }
+
+    const fn check_open(&self) -> Result<(), SnowBinError> {
+        if self.done {
+            return Err(SnowBinError::IOWriterClosed);
+        }
+        if self.pending.is_some() {
+            return Err(SnowBinError::EntryNotFinished);
+        }
+        Ok(())
+    }
+
+    fn check_header(&self, header: &str) -> Result<(), SnowBinError> {
+        check_header(self.info, header)
+    }
+
+    /// Encodes the header of an entry, checking that it was not written before if duplicates are
+    /// rejected.
+    fn entry_header(&self, header: &str) -> Result<Vec<u8>, SnowBinError> {
+        let header_bytes = encode_entry_header(self.info, header)?;
+        if self
+            .headers
+            .as_ref()
+            .is_some_and(|headers| headers.contains(&header_bytes))
+        {
+            return Err(SnowBinError::DuplicateHeader);
+        }
+        Ok(header_bytes)
+    }
+
+    /// Counts an entry with `header_bytes` once it was written in full.
+    fn record(&mut self, header_bytes: Vec<u8>) {
+        self.entries += 1;
+        if let Some(headers) = &mut self.headers {
+            headers.insert(header_bytes);
+        }
+    }
+
+    fn padding(&self) -> Vec<u8> {
+        #[allow(clippy::cast_possible_truncation)]
+        let padding = padding(self.info, self.offset) as usize;
+        vec![0_u8; padding]
+    }
+
+    pub fn write(
+        &mut self,
+        header: &str,
+        data: &[u8],
+        metadata: &SnowBinMetadata,
+    ) -> Result<(), SnowBinError> {
+        self.check_open()?;
+
+        // Check
+        self.check_header(header)?;
+        if !self.info.varint_sizes() && data.len() as u64 > max_size(self.info)? {
+            return Err(SnowBinError::DataTooLong);
+        }
+        let metadata = encode_metadata(self.info, metadata)?;
+        let header_bytes = self.entry_header(header)?;
+        let hash = self.info.dedup().then(|| hash_payload(data));
+        let reference = hash.and_then(|hash| self.payloads.get(&hash).copied());
+
+        // Write Data
+        self.put(&header_bytes).map_err(|e| e.with_header(header))?;
+
+        let length = encode_length(self.info, data.len() as u64, false)?;
+        self.put(&length).map_err(|e| e.with_header(header))?;
+        self.put(&metadata).map_err(|e| e.with_header(header))?;
+
+        if self.info.dedup() {
+            let (buffer, len) = writer::encode_varint(reference.unwrap_or(0));
+            self.put(&buffer[..len])
+                .map_err(|e| e.with_header(header))?;
+        }
+        if reference.is_some() {
+            self.record(header_bytes);
+            return Ok(());
+        }
+
+        let padding = self.padding();
+        self.put(&padding).map_err(|e| e.with_header(header))?;
+
+        let payload = self.offset;
+        self.put(data).map_err(|e| e.with_header(header))?;
+
+        if self.info.outboard() {
+            let mut outboard = OutboardHasher::new();
+            outboard.update(data);
+            self.put(&outboard.finalize())
+                .map_err(|e| e.with_header(header))?;
+        }
+
+        // Only payloads written in full can be referenced.
+        if let Some(hash) = hash {
+            self.payloads.insert(hash, payload);
+        }
+        self.record(header_bytes);
+
+        Ok(())
+    }
+
+    pub fn close(&mut self) -> Result<SnowBinWriteSummary, SnowBinError> {
+        self.check_open()?;
+        // A close that fails partway must not be run again, which could write a second hash.
+        self.done = true;
+
+        let end = if self.info.variable_headers() {
+            END_MARKER.to_le_bytes().to_vec()
+        }
+        else {
+            writer::encode_header("SNOW_END", self.info.header_size)?
+        };
+        self.put(&end)?;
+
+        // Write hash
+        let hash = self.hasher.finalize();
+        self.output.write(hash.as_bytes())?;
+        self.output.flush()?;
+
+        Ok(SnowBinWriteSummary {
+            hash: *hash.as_bytes(),
+            entries: self.entries,
+            size: self.offset + u64::from(HASH_SIZE),
+        })
+    }
+}
+
+/// An encoder that the data of an entry can be streamed into.
+pub trait Parent {
+    /// Writes the header of an entry whose data is streamed in afterwards with `write_raw`.
+    /// The length is left blank until `end_entry` fills it in.
+    fn begin_entry(&mut self, header: &str, metadata: &SnowBinMetadata)
+        -> Result<(), SnowBinError>;
+
+    /// Writes data of the pending entry.
+    fn write_raw(&mut self, data: &[u8]) -> Result<(), SnowBinError>;
+
+    /// Current byte offset from the start of the output.
+    fn position(&self) -> u64;
+
+    /// Overwrites bytes at `offset`, without moving the position.
+    fn patch(&mut self, offset: u64, data: &[u8]) -> Result<(), SnowBinError>;
+
+    /// Reads bytes at `offset` back into `buffer`, without moving the position.
+    fn read_back(&mut self, offset: u64, buffer: &mut [u8]) -> Result<(), SnowBinError>;
+
+    /// Discards the data of the pending entry from `offset` on.
+    fn truncate(&mut self, offset: u64) -> Result<(), SnowBinError>;
+
+    /// Fills in the length of the pending entry and hashes it.
+    /// If the data is too long for the data size, or the entry cannot be finished, it is discarded
+    /// instead.
+    fn end_entry(&mut self) -> Result<(), SnowBinError>;
+
+    /// Discards the pending entry, leaving the output as it was before `begin_entry`.
+    fn abort_entry(&mut self) -> Result<(), SnowBinError>;
+}
+
+impl<O: Patch> Parent for Encoder<O> {
+    fn begin_entry(
+        &mut self,
+        header: &str,
+        metadata: &SnowBinMetadata,
+    ) -> Result<(), SnowBinError> {
+        self.check_open()?;
+        self.check_header(header)?;
+        let metadata = encode_metadata(self.info, metadata)?;
+        let length = encode_length(self.info, 0, true)?;
+
+        let mut pending = Pending {
+            start: self.offset,
+            hasher: self.hasher.clone(),
+            header: self.entry_header(header)?,
+            length_offset: 0,
+            data_start: 0,
+        };
+        if let Err(e) = self.start_entry(&mut pending, &length, &metadata) {
+            let _ = self.discard(pending);
+            return Err(e.with_header(header));
+        }
+
+        self.pending = Some(pending);
+        Ok(())
+    }
+
+    fn write_raw(&mut self, data: &[u8]) -> Result<(), SnowBinError> {
+        if self.pending.is_none() {
+            return Err(SnowBinError::IOWriterClosed);
+        }
+        self.raw(data)
+    }
+
+    fn position(&self) -> u64 {
+        self.offset
+    }
+
+    fn patch(&mut self, offset: u64, data: &[u8]) -> Result<(), SnowBinError> {
+        self.output.patch(offset, data)
+    }
+
+    fn read_back(&mut self, offset: u64, buffer: &mut [u8]) -> Result<(), SnowBinError> {
+        self.output.read_back(offset, buffer)
+    }
+
+    fn truncate(&mut self, offset: u64) -> Result<(), SnowBinError> {
+        match &self.pending {
+            Some(pending) if offset >= pending.data_start => {
+                self.output.truncate(offset)?;
+                self.offset = offset;
+                Ok(())
+            }
+            _ => Err(SnowBinError::IOWriterClosed),
+        }
+    }
+
+    fn end_entry(&mut self) -> Result<(), SnowBinError> {
+        let Some(pending) = self.pending.take()
+        else {
+            return Err(SnowBinError::IOWriterClosed);
+        };
+
+        // A failed entry is discarded, so that the parent can still be written to and closed.
+        let size = self.offset - pending.data_start;
+        if !self.info.varint_sizes() && size > max_size(self.info)? {
+            self.discard(pending)?;
+            return Err(SnowBinError::DataTooLong);
+        }
+        if let Err(e) = self.finish_entry(&pending, size) {
+            let _ = self.discard(pending);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    fn abort_entry(&mut self) -> Result<(), SnowBinError> {
+        let Some(pending) = self.pending.take()
+        else {
+            return Err(SnowBinError::IOWriterClosed);
+        };
+        self.discard(pending)
+    }
+}
+
+impl<O: Patch> Encoder<O> {
+    /// Writes everything of `pending` up to its data, with a blank `length`.
+    fn start_entry(
+        &mut self,
+        pending: &mut Pending,
+        length: &[u8],
+        metadata: &[u8],
+    ) -> Result<(), SnowBinError> {
+        self.put(&pending.header)?;
+
+        // The length, metadata and padding are hashed once the length is known.
+        pending.length_offset = self.offset;
+        self.raw(length)?;
+        self.raw(metadata)?;
+        if self.info.dedup() {
+            // Streamed data is not known yet, so it is always stored.
+            self.raw(&[0])?;
+        }
+        let padding = self.padding();
+        self.raw(&padding)?;
+        pending.data_start = self.offset;
+        Ok(())
+    }
+
+    /// Fills in the length of `pending`, which has `size` bytes of data, and hashes it.
+    fn finish_entry(&mut self, pending: &Pending, size: u64) -> Result<(), SnowBinError> {
+        let length = encode_length(self.info, size, true)?;
+        self.output.patch(pending.length_offset, &length)?;
+
+        // Hash everything between the header and the end of the data, in order.
+        let mut outboard = OutboardHasher::new();
+        let mut buffer = vec![0_u8; HASH_CHUNK];
+        let mut offset = pending.length_offset;
+        while offset < self.offset {
+            #[allow(clippy::cast_possible_truncation)]
+            let chunk = (self.offset - offset).min(HASH_CHUNK as u64) as usize;
+            self.output.read_back(offset, &mut buffer[..chunk])?;
+            update(&mut self.hasher, &buffer[..chunk]);
+
+            #[allow(clippy::cast_possible_truncation)]
+            let data = pending.data_start.saturating_sub(offset).min(chunk as u64) as usize;
+            outboard.update(&buffer[data..chunk]);
+            offset += chunk as u64;
+        }
+
+        if self.info.outboard() || self.info.dedup() {
+            let outboard = outboard.finalize();
+            if self.info.outboard() {
+                self.put(&outboard)?;
+            }
+            if self.info.dedup() {
+                // The outboard ends with the hash of the data.
+                let mut hash = [0_u8; HASH_SIZE as usize];
+                hash.copy_from_slice(&outboard[outboard.len() - HASH_SIZE as usize..]);
+                self.payloads.entry(hash).or_insert(pending.data_start);
+            }
+        }
+        self.record(pending.header.clone());
+
+        Ok(())
+    }
+
+    /// Discards `pending`, moving the output back to where the entry started.
+    fn discard(&mut self, pending: Pending) -> Result<(), SnowBinError> {
+        self.hasher = pending.hasher;
+        self.output.truncate(pending.start)?;
+        self.offset = pending.start;
+        Ok(())
+    }
+}
+
+fn check_header(info: SnowBinInfo, header: &str) -> Result<(), SnowBinError> {
+    if header.len() > info.header_size as usize {
+        return Err(SnowBinError::HeaderTooLong);
+    }
+    if let Some(separator) = info.separator() {
+        if !path::is_valid(header, separator) {
+            return Err(SnowBinError::InvalidPath);
+        }
+    }
+    Ok(())
+}
+
+/// Checks that an entry with `header` and `size` bytes of data can be written with `info`, without
+/// writing it.
+#[cfg(feature = "std")]
+pub fn check_entry(info: SnowBinInfo, header: &str, size: u64) -> Result<(), SnowBinError> {
+    check_header(info, header)?;
+    encode_entry_header(info, header)?;
+    if !info.varint_sizes() && size > max_size(info)? {
+        return Err(SnowBinError::DataTooLong);
+    }
+    Ok(())
+}
+
+/// Encodes a payload length for `info`. If `padded` the length takes up its maximum width, so it
+/// can be filled in later.
+fn encode_length(info: SnowBinInfo, size: u64, padded: bool) -> Result<Vec<u8>, SnowBinError> {
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(match info.data_size {
+        _ if info.varint_sizes() => {
+            let (buffer, len) = writer::encode_varint(size);
+            if padded {
+                writer::pad_varint(buffer, len).to_vec()
+            }
+            else {
+                buffer[..len].to_vec()
+            }
+        }
+        8 => (size as u8).to_le_bytes().to_vec(),
+        16 => (size as u16).to_le_bytes().to_vec(),
+        32 => (size as u32).to_le_bytes().to_vec(),
+        64 => size.to_le_bytes().to_vec(),
+        _ => return Err(SnowBinError::DataSizeNotAllowed),
+    })
+}
+
+/// Encodes the metadata block of the file, which is left out if `info` does not have one.
+pub fn encode_file_metadata(
+    info: SnowBinInfo,
+    metadata: &SnowBinMetadata,
+) -> Result<Vec<u8>, SnowBinError> {
+    if info.file_metadata() {
+        Ok(metadata.encode())
+    }
+    else if metadata.is_empty() {
+        Ok(Vec::new())
+    }
+    else {
+        Err(SnowBinError::MetadataNotEnabled)
+    }
+}
+
+/// Encodes the metadata block of an entry, which is left out if `info` does not have one.
+fn encode_metadata(info: SnowBinInfo, metadata: &SnowBinMetadata) -> Result<Vec<u8>, SnowBinError> {
+    if info.entry_metadata() {
+        Ok(metadata.encode())
+    }
+    else if metadata.is_empty() {
+        Ok(Vec::new())
+    }
+    else {
+        Err(SnowBinError::MetadataNotEnabled)
+    }
+}
+
+/// Encodes `header` as it is stored in front of an entry.
+fn encode_entry_header(info: SnowBinInfo, header: &str) -> Result<Vec<u8>, SnowBinError> {
+    if !info.variable_headers() {
+        return writer::encode_header(header, info.header_size);
+    }
+
+    match u32::try_from(header.len()) {
+        Ok(len) if len <= info.header_size && len != END_MARKER => {
+            Ok([&len.to_le_bytes(), header.as_bytes()].concat())
+        }
+        _ => Err(SnowBinError::HeaderTooLong),
+    }
+}
+
+/// Bytes of padding needed at `offset` for the next payload to be aligned.
+pub const fn padding(info: SnowBinInfo, offset: u64) -> u64 {
+    let alignment = info.alignment() as u64;
+    (alignment - offset % alignment) % alignment
+}
+
+/// Largest payload length that fits in the data size of `info`.
+pub const fn max_size(info: SnowBinInfo) -> Result<u64, SnowBinError> {
+    Ok(match info.data_size {
+        8 => u8::MAX as u64,
+        16 => u16::MAX as u64,
+        32 => u32::MAX as u64,
+        64 => u64::MAX,
+        _ => return Err(SnowBinError::DataSizeNotAllowed),
+    })
+}
+
+/// Hashes a payload with blake3, to find identical payloads.
+fn hash_payload(data: &[u8]) -> [u8; HASH_SIZE as usize] {
+    let mut hasher = blake3::Hasher::new();
+    update(&mut hasher, data);
+    *hasher.finalize().as_bytes()
+}
+
+/// Hashes `data`, on several threads if it is large and the `parallel` feature is enabled.
+/// The hash is the same either way.
+pub fn update(hasher: &mut blake3::Hasher, data: &[u8]) {
+    #[cfg(feature = "parallel")]
+    if data.len() >= PARALLEL_MIN {
+        hasher.update_rayon(data);
+        return;
+    }
+
+    hasher.update(data);
+}
+
+/// Hashes everything but the trailing hash and checks it against the trailing hash.
+pub fn verify<I: Input>(input: &mut I, limits: SnowBinLimits) -> Result<(), SnowBinError> {
+    let len = input.len()?;
+    let body = body_len(len, limits)?;
+
+    input.seek(0)?;
+    let mut hasher = blake3::Hasher::new();
+    #[allow(clippy::cast_possible_truncation)]
+    let mut buffer = vec![0_u8; body.min(VERIFY_CHUNK as u64) as usize];
+    let mut remaining = body;
+    while remaining > 0 {
+        #[allow(clippy::cast_possible_truncation)]
+        let chunk = remaining.min(VERIFY_CHUNK as u64) as usize;
+        input.read(&mut buffer[..chunk])?;
+        update(&mut hasher, &buffer[..chunk]);
+        remaining -= chunk as u64;
+    }
+
+    let mut read_hash = [0_u8; HASH_SIZE as usize];
+    input.read(&mut read_hash)?;
+
+    if hasher.finalize() != read_hash {
+        return Err(SnowBinError::HashDoesNotMatch);
+    }
+
+    Ok(())
+}
+
+/// Same as `verify`, but hashes `data` in place.
+pub fn verify_slice(data: &[u8], limits: SnowBinLimits) -> Result<(), SnowBinError> {
+    #[allow(clippy::cast_possible_truncation)]
+    let body = body_len(data.len() as u64, limits)? as usize;
+
+    let mut hasher = blake3::Hasher::new();
+    update(&mut hasher, &data[..body]);
+
+    if hasher.finalize() != data[body..] {
+        return Err(SnowBinError::HashDoesNotMatch);
+    }
+
+    Ok(())
+}
+
+/// Length of a file of `len` bytes without its trailing hash.
+fn body_len(len: u64, limits: SnowBinLimits) -> Result<u64, SnowBinError> {
+    check(SnowBinLimit::TotalBytes, len, limits.max_total_bytes)?;
+    len.checked_sub(u64::from(HASH_SIZE))
+        .ok_or_else(|| SnowBinError::UnexpectedEof {
+            offset: 0,
+            expected: u64::from(HASH_SIZE),
+            actual: len,
+        })
+}
+
+/// Reads the file info from the start of `input`.
+pub fn read_info<I: Input>(
+    input: &mut I,
+    limits: SnowBinLimits,
+) -> Result<SnowBinInfo, SnowBinError> {
+    check(
+        SnowBinLimit::TotalBytes,
+        input.len()?,
+        limits.max_total_bytes,
+    )?;
+    input.seek(0)?;
+
+    let snow_header = reader::read_header(input, 8)?;
+    if !snow_header.eq("SNOW_BIN") {
+        return Err(SnowBinError::MalformedHeader);
+    }
+
+    let version = reader::read_u64(input)?;
+    if !(MIN_VERSION_SPEC..=VERSION_SPEC).contains(&version) {
+        return Err(SnowBinError::WrongSpecVersion);
+    }
+
+    let header_size = reader::read_u32(input)?;
+    check(
+        SnowBinLimit::HeaderSize,
+        u64::from(header_size),
+        u64::from(limits.max_header_size),
+    )?;
+
+    let data_size = reader::read_u8(input)?;
+    match data_size {
+        8 | 16 | 32 | 64 => (),
+        _ => return Err(SnowBinError::DataSizeNotAllowed),
+    }
+
+    let flags = if version >= 3 { reader::read_u32(input)? } else { 0 };
+    let info = SnowBinInfo {
+        header_size,
+        data_size,
+        flags,
+    };
+    check_flags(info, version)?;
+
+    Ok(info)
+}
+
+/// Checks that the flags of `info` can be read and match the Spec `version` of the file.
+pub fn check_flags(info: SnowBinInfo, version: u64) -> Result<(), SnowBinError> {
+    if info.flags & !KNOWN_FLAGS != 0 {
+        return Err(SnowBinError::UnsupportedFlags { flags: info.flags });
+    }
+    if info.version() != version {
+        return Err(SnowBinError::WrongSpecVersion);
+    }
+    if info.alignment() > MAX_ALIGNMENT {
+        return Err(SnowBinError::AlignmentNotAllowed);
+    }
+    if info
+        .separator()
+        .is_some_and(|separator| !separator.is_ascii_punctuation())
+    {
+        return Err(SnowBinError::SeparatorNotAllowed);
+    }
+
+    Ok(())
+}
+
+/// Reads the file metadata block after the file info.
+/// Files without file metadata have an empty block.
+pub fn read_file_metadata<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+) -> Result<SnowBinMetadata, SnowBinError> {
+    let (_, size) = file_metadata_range(input, info, limits)?;
+
+    // The size was checked against the limits and the remaining input.
+    #[allow(clippy::cast_possible_truncation)]
+    let mut buffer = vec![0_u8; size as usize];
+    input.read(&mut buffer)?;
+
+    SnowBinMetadata::decode(&buffer)
+}
+
+/// Offset and length of the file metadata block, leaving `input` at its start.
+fn file_metadata_range<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+) -> Result<(u64, u64), SnowBinError> {
+    input.seek(info.data_start())?;
+    if !info.file_metadata() {
+        return Ok((info.data_start(), 0));
+    }
+
+    let size = reader::read_varint(input)?;
+    check(SnowBinLimit::MetadataSize, size, limits.max_metadata_size)?;
+    let offset = input.position();
+    let remaining = input.len()?.saturating_sub(offset);
+    if size > remaining {
+        return Err(SnowBinError::UnexpectedEof {
+            offset,
+            expected: size,
+            actual: remaining,
+        });
+    }
+
+    Ok((offset, size))
+}
+
+/// Reads the header in front of an entry, or `None` if the end of the entries was reached.
+/// `len` is the total length of `input`, used to reject headers longer than what is left.
+pub fn read_entry_header<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    len: u64,
+) -> Result<Option<String>, SnowBinError> {
+    let header_len = if info.variable_headers() {
+        let header_len = reader::read_u32(input)?;
+        if header_len == END_MARKER {
+            return Ok(None);
+        }
+        if header_len > info.header_size {
+            return Err(SnowBinError::MalformedHeader);
+        }
+        header_len
+    }
+    else {
+        info.header_size
+    };
+
+    let remaining = len.saturating_sub(input.position());
+    if u64::from(header_len) > remaining {
+        return Err(SnowBinError::UnexpectedEof {
+            offset: input.position(),
+            expected: u64::from(header_len),
+            actual: remaining,
+        });
+    }
+
+    let header = reader::read_header(input, header_len)?;
+    if !info.variable_headers() && header.starts_with("SNOW_END") {
+        return Ok(None);
+    }
+
+    Ok(Some(header))
+}
+
+/// Reads a payload length of the data size in `info`.
+pub fn read_length<I: Input>(input: &mut I, info: SnowBinInfo) -> Result<u64, SnowBinError> {
+    Ok(match info.data_size {
+        _ if info.varint_sizes() => reader::read_varint(input)?,
+        8 => u64::from(reader::read_u8(input)?),
+        16 => u64::from(reader::read_u16(input)?),
+        32 => u64::from(reader::read_u32(input)?),
+        64 => reader::read_u64(input)?,
+        _ => return Err(SnowBinError::DataSizeNotAllowed),
+    })
+}
+
+/// An entry found while walking over a file.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// The header as stored, including any padding.
+    pub header: String,
+    /// Offset of the payload.
+    pub offset: u64,
+    /// Length of the payload.
+    pub size: u64,
+    /// Offset of the metadata block, after its length.
+    pub metadata_offset: u64,
+    /// Length of the metadata block, 0 if the file has no entry metadata.
+    pub metadata_size: u64,
+}
+
+impl Entry {
+    /// Range of the payload.
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn range(&self) -> Range<usize> {
+        self.offset as usize..(self.offset + self.size) as usize
+    }
+
+    /// The header without the padding added by fixed size headers.
+    pub fn name(&self, info: SnowBinInfo) -> &str {
+        if info.variable_headers() {
+            &self.header
+        }
+        else {
+            self.header.trim_end_matches(' ')
+        }
+    }
+}
+
+/// Walks over the entries of a file one at a time, checking each against the limits.
+#[derive(Clone, Debug)]
+pub struct Walker {
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+    len: u64,
+    next: u64,
+    entries: u64,
+    /// Sizes of the payloads stored so far by their offset, if identical payloads are stored once.
+    payloads: BTreeMap<u64, u64>,
+}
+
+impl Walker {
+    pub fn new<I: Input>(
+        input: &mut I,
+        info: SnowBinInfo,
+        limits: SnowBinLimits,
+    ) -> Result<Self, SnowBinError> {
+        let (offset, size) = file_metadata_range(input, info, limits)?;
+
+        Ok(Self {
+            info,
+            limits,
+            len: input.len()?,
+            next: offset + size,
+            entries: 0,
+            payloads: BTreeMap::new(),
+        })
+    }
+
+    /// Offset of the next entry to be read.
+    pub const fn position(&self) -> u64 {
+        self.next
+    }
+
+    /// Reads the next entry, or `None` if the end of the entries was reached.
+    pub fn next<I: Input>(&mut self, input: &mut I) -> Result<Option<Entry>, SnowBinError> {
+        let start = self.next;
+        input.seek(start)?;
+
+        let Some(header) = read_entry_header(input, self.info, self.len)?
+        else {
+            return Ok(None);
+        };
+
+        self.entries += 1;
+        check(SnowBinLimit::Entries, self.entries, self.limits.max_entries)?;
+
+        let size = read_length(input, self.info).map_err(|e| e.with_header(&header))?;
+        let metadata_size = if self.info.entry_metadata() {
+            let metadata_size = reader::read_varint(input).map_err(|e| e.with_header(&header))?;
+            check(
+                SnowBinLimit::MetadataSize,
+                metadata_size,
+                self.limits.max_metadata_size,
+            )
+            .map_err(|e| e.with_header(&header))?;
+            metadata_size
+        }
+        else {
+            0
+        };
+        let metadata_offset = input.position();
+        let remaining = self.len.saturating_sub(metadata_offset);
+        if metadata_size > remaining {
+            return Err(SnowBinError::UnexpectedEof {
+                offset: metadata_offset,
+                expected: metadata_size,
+                actual: remaining,
+            });
+        }
+        input.skip(metadata_size)?;
+        check(SnowBinLimit::EntrySize, size, self.limits.max_entry_size)
+            .map_err(|e| e.with_header(&header))?;
+        let outboard = if self.info.outboard() { outboard::len(size) } else { 0 };
+
+        if self.info.dedup() {
+            let reference_offset = input.position();
+            let reference = reader::read_varint(input).map_err(|e| e.with_header(&header))?;
+            if reference != 0 {
+                // The reference must be to the payload of an earlier entry, of the same size.
+                if self.payloads.get(&reference) != Some(&size) {
+                    return Err(SnowBinError::InvalidReference {
+                        offset: reference_offset,
+                    });
+                }
+
+                self.next = input.position();
+                return Ok(Some(Entry {
+                    header,
+                    offset: reference,
+                    size,
+                    metadata_offset,
+                    metadata_size,
+                }));
+            }
+        }
+
+        input.skip(padding(self.info, input.position()))?;
+        let offset = input.position();
+        let remaining = self.len.saturating_sub(offset);
+        if size.saturating_add(outboard) > remaining {
+            return Err(SnowBinError::UnexpectedEof {
+                offset,
+                expected: size.saturating_add(outboard),
+                actual: remaining,
+            });
+        }
+
+        if self.info.dedup() {
+            self.payloads.insert(offset, size);
+        }
+        self.next = offset + size + outboard;
+        Ok(Some(Entry {
+            header,
+            offset,
+            size,
+            metadata_offset,
+            metadata_size,
+        }))
+    }
+}
+
+/// Separator used for path lookups, `/` if the file does not set one.
+pub fn separator(info: SnowBinInfo) -> char {
+    info.separator().unwrap_or('/')
+}
+
+/// Lists every header, without padding, in file order.
+pub fn headers<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+) -> Result<Vec<String>, SnowBinError> {
+    let mut headers = Vec::new();
+    let mut walker = Walker::new(input, info, limits)?;
+    while let Some(entry) = walker.next(input)? {
+        headers.push(String::from(entry.name(info)));
+    }
+
+    Ok(headers)
+}
+
+/// Lists the paths of the direct children of `prefix`, in the order they first appear.
+pub fn children<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+    prefix: &str,
+) -> Result<Vec<String>, SnowBinError> {
+    let separator = separator(info);
+
+    let mut seen = BTreeSet::new();
+    let mut children = Vec::new();
+    let mut walker = Walker::new(input, info, limits)?;
+    while let Some(entry) = walker.next(input)? {
+        if let Some(child) = path::child(entry.name(info), prefix, separator) {
+            if seen.insert(String::from(child)) {
+                children.push(String::from(child));
+            }
+        }
+    }
+
+    Ok(children)
+}
+
+/// Fails with `DuplicateHeader` if any header is used by more than one entry.
+#[cfg(feature = "std")]
+pub fn check_duplicates<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+) -> Result<(), SnowBinError> {
+    let mut seen = BTreeSet::new();
+    let mut walker = Walker::new(input, info, limits)?;
+    while let Some(entry) = walker.next(input)? {
+        if !seen.insert(entry.header) {
+            return Err(SnowBinError::DuplicateHeader);
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the entry for `header`, leaving `input` at the start of its payload.
+/// Returns the length of the payload, which is checked against `limits` and the remaining input.
+pub fn find<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+    header: &str,
+) -> Result<u64, SnowBinError> {
+    let entry = find_entry(input, info, limits, header)?;
+    input.seek(entry.offset)?;
+    Ok(entry.size)
+}
+
+/// Finds the entry for `header` without reading its payload.
+pub fn find_entry<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+    header: &str,
+) -> Result<Entry, SnowBinError> {
+    let header = if info.variable_headers() {
+        if header.len() > info.header_size as usize {
+            return Err(SnowBinError::HeaderTooLong);
+        }
+        header.as_bytes().to_vec()
+    }
+    else {
+        writer::encode_header(header, info.header_size)?
+    };
+
+    let mut walker = Walker::new(input, info, limits)?;
+    loop {
+        let Some(entry) = walker.next(input)?
+        else {
+            return Err(SnowBinError::ReachedEOF);
+        };
+
+        if entry.header.as_bytes() == header {
+            return Ok(entry);
+        }
+    }
+}
+
+/// Reads the metadata of the entry for `header`, without reading its payload.
+pub fn metadata<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+    header: &str,
+) -> Result<SnowBinMetadata, SnowBinError> {
+    let entry = find_entry(input, info, limits, header)?;
+    entry_metadata(input, &entry).map_err(|e| e.with_header(header))
+}
+
+/// Reads the metadata of `entry`, without reading its payload.
+pub fn entry_metadata<I: Input>(
+    input: &mut I,
+    entry: &Entry,
+) -> Result<SnowBinMetadata, SnowBinError> {
+    // The size was checked against the limits and the remaining input by the walker.
+    #[allow(clippy::cast_possible_truncation)]
+    let mut buffer = vec![0_u8; entry.metadata_size as usize];
+    input.seek(entry.metadata_offset)?;
+    input.read(&mut buffer)?;
+
+    SnowBinMetadata::decode(&buffer)
+}
+
+/// Hashes the payload of `entry` with blake3. Files with outboard hashes store it, so it is read
+/// instead of hashing the payload.
+pub fn hash_entry<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    entry: &Entry,
+) -> Result<[u8; HASH_SIZE as usize], SnowBinError> {
+    let mut hash = [0_u8; HASH_SIZE as usize];
+    if info.outboard() {
+        input.seek(entry.offset + entry.size + outboard::len(entry.size) - u64::from(HASH_SIZE))?;
+        input.read(&mut hash)?;
+        return Ok(hash);
+    }
+
+    let mut hasher = blake3::Hasher::new();
+    #[allow(clippy::cast_possible_truncation)]
+    let mut buffer = vec![0_u8; entry.size.min(HASH_CHUNK as u64) as usize];
+    let mut remaining = entry.size;
+    input.seek(entry.offset)?;
+    while remaining > 0 {
+        #[allow(clippy::cast_possible_truncation)]
+        let chunk = remaining.min(HASH_CHUNK as u64) as usize;
+        input.read(&mut buffer[..chunk])?;
+        update(&mut hasher, &buffer[..chunk]);
+        remaining -= chunk as u64;
+    }
+    hash.copy_from_slice(hasher.finalize().as_bytes());
+
+    Ok(hash)
+}
+
+const fn check(limit: SnowBinLimit, value: u64, max: u64) -> Result<(), SnowBinError> {
+    if value > max {
+        return Err(SnowBinError::LimitExceeded { limit, value, max });
+    }
+    Ok(())
+}
@@ -0,0 +1,216 @@
+//! Write-ahead journal of the changes made to a `SnowBinMap` since it was last flushed.
+//!
+//! The journal is a sidecar file next to the map's file. It starts with `SNOW_JNL` and the hash at
+//! the end of the file it applies to, followed by one record per change:
+//!
+//! - 1 byte operation, 1 for insert and 2 for remove.
+//! - u32 key length and the key.
+//! - u64 data length and the data, which is empty for removals.
+//! - 32 byte blake3 hash of the record up to here.
+//!
+//! Each record is synced before the change is made in memory. A record that was only partially
+//! written, and everything after it, is discarded when the journal is replayed. Changes made after
+//! a replay are appended to the complete records, so the journal keeps every change until the map
+//! is flushed.
+
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{error::SnowBinError, file, HASH_SIZE};
+
+const MAGIC: &[u8; 8] = b"SNOW_JNL";
+const INSERT: u8 = 1;
+const REMOVE: u8 = 2;
+
+/// A change recorded in a journal.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Change {
+    Insert(String, Vec<u8>),
+    Remove(String),
+}
+
+/// Appends changes to the journal of a file, creating it on the first change.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    base: [u8; HASH_SIZE as usize],
+    file: Option<File>,
+    offset: u64,
+}
+
+impl Journal {
+    /// Creates a journal for changes to the file at `path`, whose trailing hash is `base`.
+    /// `end` is the length of the complete records of an existing journal to append to, as returned
+    /// by `recover`, or 0 to start a new journal.
+    pub fn new(path: &Path, base: [u8; HASH_SIZE as usize], end: u64) -> Self {
+        Self {
+            path: journal_path(path),
+            base,
+            file: None,
+            offset: end,
+        }
+    }
+
+    pub fn insert(&mut self, key: &str, data: &[u8]) -> Result<(), SnowBinError> {
+        self.append(INSERT, key, data)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Result<(), SnowBinError> {
+        self.append(REMOVE, key, &[])
+    }
+
+    fn append(&mut self, op: u8, key: &str, data: &[u8]) -> Result<(), SnowBinError> {
+        let mut record = Vec::with_capacity(key.len() + data.len() + 45);
+        if self.offset == 0 {
+            record.extend_from_slice(MAGIC);
+            record.extend_from_slice(&self.base);
+        }
+        let start = record.len();
+        record.push(op);
+        #[allow(clippy::cast_possible_truncation)]
+        record.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        record.extend_from_slice(key.as_bytes());
+        record.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        record.extend_from_slice(data);
+        let hash = blake3::hash(&record[start..]);
+        record.extend_from_slice(hash.as_bytes());
+
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(self.open().map_err(|source| {
+                SnowBinError::CouldNotCreateOrOpenFile {
+                    path: self.path.clone(),
+                    source,
+                }
+            })?),
+        };
+        if let Err(source) = file.write_all(&record).and_then(|()| file.sync_data()) {
+            // Cut off the partial record, so that records appended later can still be replayed.
+            let _ = file.set_len(self.offset);
+            let _ = file.seek(SeekFrom::Start(self.offset));
+            return Err(SnowBinError::IOWriteError {
+                offset: self.offset,
+                header: Some(String::from(key)),
+                source,
+            });
+        }
+        self.offset += record.len() as u64;
+
+        Ok(())
+    }
+
+    /// Creates the journal, or opens the existing one after its last complete record.
+    fn open(&self) -> io::Result<File> {
+        if self.offset == 0 {
+            let file = File::create(&self.path)?;
+            // The journal must still be there after a crash, not only its records.
+            file::sync_dir(&self.path)?;
+            return Ok(file);
+        }
+
+        let mut file = OpenOptions::new().write(true).open(&self.path)?;
+        file.set_len(self.offset)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        Ok(file)
+    }
+}
+
+/// Path of the journal of the file at `path`, next to it.
+pub fn journal_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.file_name().unwrap_or_default());
+    name.push(".journal");
+    path.with_file_name(name)
+}
+
+/// Deletes the journal of the file at `path`, if it has one.
+pub fn remove(path: &Path) -> Result<(), SnowBinError> {
+    let path = journal_path(path);
+    match fs::remove_file(&path) {
+        Err(source) if source.kind() != ErrorKind::NotFound => {
+            Err(SnowBinError::CouldNotCreateOrOpenFile { path, source })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reads the hash at the end of the file at `path`.
+pub fn file_hash(path: &Path) -> Result<[u8; HASH_SIZE as usize], SnowBinError> {
+    let mut hash = [0_u8; HASH_SIZE as usize];
+    File::open(path)
+        .and_then(|mut file| {
+            file.seek(SeekFrom::End(-i64::from(HASH_SIZE)))?;
+            file.read_exact(&mut hash)
+        })
+        .map_err(|source| SnowBinError::CouldNotCreateOrOpenFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+    Ok(hash)
+}
+
+/// Reads the complete records of the journal of the file at `path`, and the length of the journal
+/// up to the end of the last one. Returns nothing if there is no journal, or if it was written for
+/// a file whose trailing hash is not `base`, such as when the changes were already applied but the
+/// journal was not deleted yet.
+pub fn recover(
+    path: &Path,
+    base: [u8; HASH_SIZE as usize],
+) -> Result<(Vec<Change>, u64), SnowBinError> {
+    let path = journal_path(path);
+    let journal = match fs::read(&path) {
+        Ok(journal) => journal,
+        Err(source) if source.kind() == ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(source) => return Err(SnowBinError::CouldNotCreateOrOpenFile { path, source }),
+    };
+
+    let header = MAGIC.len() + HASH_SIZE as usize;
+    if journal.len() < header
+        || &journal[..MAGIC.len()] != MAGIC
+        || journal[MAGIC.len()..header] != base
+    {
+        return Ok((Vec::new(), 0));
+    }
+
+    let mut changes = Vec::new();
+    let mut end = header;
+    while let Some((change, len)) = read_record(&journal[end..]) {
+        changes.push(change);
+        end += len;
+    }
+    if changes.is_empty() {
+        return Ok((changes, 0));
+    }
+
+    Ok((changes, end as u64))
+}
+
+/// Reads the record at the start of `data` and its length, or `None` if it is incomplete or does
+/// not match its hash.
+fn read_record(data: &[u8]) -> Option<(Change, usize)> {
+    let op = *data.first()?;
+    let key_len = u32::from_le_bytes(data.get(1..5)?.try_into().ok()?) as usize;
+    let key_end = 5_usize.checked_add(key_len)?;
+    let key = data.get(5..key_end)?;
+    let data_len = u64::from_le_bytes(data.get(key_end..key_end + 8)?.try_into().ok()?);
+    let data_start = key_end + 8;
+    let data_end = data_start.checked_add(usize::try_from(data_len).ok()?)?;
+    let payload = data.get(data_start..data_end)?;
+    let hash = data.get(data_end..data_end.checked_add(HASH_SIZE as usize)?)?;
+    if blake3::hash(&data[..data_end]) != *hash {
+        return None;
+    }
+
+    let key = String::from(std::str::from_utf8(key).ok()?);
+    let change = match op {
+        INSERT => Change::Insert(key, payload.to_vec()),
+        REMOVE => Change::Remove(key),
+        _ => return None,
+    };
+
+    Some((change, data_end + HASH_SIZE as usize))
+}
@@ -1,24 +1,78 @@
 #![warn(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 //! Easy to use binary file writer and reader with its own format.
+//!
+//! The format logic works on byte slices and only needs `alloc`, so it is available without the
+//! default `std` feature through `SnowBinEncoder` and `SnowBinDecoder`. The file based
+//! `SnowBinWriter` and `SnowBinReader` are layered on top and require `std`.
 
+extern crate alloc;
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+mod archive;
+mod builder;
+mod diff;
 mod error;
+#[cfg(feature = "std")]
+mod file;
+mod format;
+#[cfg(feature = "std")]
+mod journal;
+mod limits;
+#[cfg(feature = "std")]
+mod map;
+mod memory;
+mod metadata;
+mod nested;
+#[cfg(feature = "std")]
+mod options;
+mod outboard;
+mod path;
+mod plan;
+mod probe;
 mod reader;
+mod salvage;
+mod schema;
+#[cfg(all(feature = "std", any(unix, windows)))]
+mod shared;
+mod summary;
 mod writer;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests;
 
-use std::{
-    fs::File,
-    io::{Seek, SeekFrom},
-    path::PathBuf,
+#[cfg(any(feature = "tar", feature = "zip"))]
+pub use crate::archive::SnowBinArchiveReport;
+#[cfg(all(feature = "std", any(unix, windows)))]
+pub use crate::shared::SnowBinSharedReader;
+pub use crate::{
+    builder::SnowBinInfoBuilder,
+    diff::{SnowBinChange, SnowBinDiff, SnowBinDiffLine, SnowBinEntryDiff},
+    error::SnowBinError,
+    limits::{SnowBinLimit, SnowBinLimits},
+    memory::{SnowBinDecoder, SnowBinDecoderSubtree, SnowBinEncoder},
+    metadata::SnowBinMetadata,
+    nested::SnowBinNestedWriter,
+    plan::{SnowBinPlan, SnowBinStagedWriter},
+    probe::{probe, SnowBinProbe},
+    salvage::{SnowBinSalvage, SnowBinSalvagedEntry},
+    schema::{SnowBinField, SnowBinSchema, SnowBinValueType, SnowBinViolation},
+    summary::SnowBinWriteSummary,
+};
+#[cfg(feature = "std")]
+pub use crate::{
+    file::{SnowBinDropAction, SnowBinEntryReader, SnowBinReader, SnowBinSubtree, SnowBinWriter},
+    map::SnowBinMap,
+    options::{SnowBinDuplicates, SnowBinOpenOptions, SnowBinVerification},
+    probe::probe_path,
 };
-
-pub use crate::error::SnowBinError;
 
 /// The version of the Spec that this library can interact with.
-pub const VERSION_SPEC: u64 = 2; // Snow Binary File Format
+/// Files that do not use any Spec 3 options are written as Spec 2.
+pub const VERSION_SPEC: u64 = 3; // Snow Binary File Format
+/// The oldest version of the Spec that this library can read.
+pub const MIN_VERSION_SPEC: u64 = 2;
 
 const DEFAULT_HEADER_SIZE: u32 = 8;
 const DATA_SIZES: [u8; 4] = [8, 16, 32, 64];
@@ -26,8 +80,32 @@ const DEFAULT_DATA_SIZE: usize = 3;
 
 // In bytes.
 const DATA_START: u64 = 21;
+const DATA_START_V3: u64 = 25;
 const HASH_SIZE: u32 = 32;
 
+// Spec 3 flags.
+const FLAG_VARIABLE_HEADERS: u32 = 1;
+const FLAG_VARINT_SIZES: u32 = 1 << 1;
+const FLAG_ENTRY_METADATA: u32 = 1 << 2;
+const FLAG_FILE_METADATA: u32 = 1 << 3;
+const FLAG_OUTBOARD: u32 = 1 << 4;
+const FLAG_DEDUP: u32 = 1 << 5;
+// Bits 8 to 12 hold log2 of the payload alignment.
+const ALIGNMENT_SHIFT: u32 = 8;
+const ALIGNMENT_MASK: u32 = 0x1f << ALIGNMENT_SHIFT;
+const MAX_ALIGNMENT: u32 = 1 << 16;
+// Bits 16 to 23 hold the path separator, 0 if headers are not paths.
+const SEPARATOR_SHIFT: u32 = 16;
+const SEPARATOR_MASK: u32 = 0xff << SEPARATOR_SHIFT;
+const KNOWN_FLAGS: u32 = FLAG_VARIABLE_HEADERS
+    | FLAG_VARINT_SIZES
+    | FLAG_ENTRY_METADATA
+    | FLAG_FILE_METADATA
+    | FLAG_OUTBOARD
+    | FLAG_DEDUP
+    | ALIGNMENT_MASK
+    | SEPARATOR_MASK;
+
 /// Holds information used by `SnowBinWriter` to create and write to files.
 /// Default returns `SnowBinInfo` with a header size of 8 and a data size of 64.
 /// # Example
@@ -40,6 +118,7 @@ const HASH_SIZE: u32 = 32;
 pub struct SnowBinInfo {
     header_size: u32,
     data_size: u8,
+    flags: u32,
 }
 
 impl SnowBinInfo {
@@ -71,356 +150,257 @@ impl SnowBinInfo {
         Ok(Self {
             header_size,
             data_size,
+            flags: 0,
         })
     }
-}
 
-impl Default for SnowBinInfo {
-    fn default() -> Self {
-        Self {
-            header_size: DEFAULT_HEADER_SIZE,
-            data_size: DATA_SIZES[DEFAULT_DATA_SIZE],
-        }
+    /// Creates a `SnowBinInfoBuilder`, which sets every option before checking them in `build`.
+    /// # Example
+    /// ```
+    /// use snowbinary::SnowBinInfo;
+    ///
+    /// let info = SnowBinInfo::builder()
+    ///     .header_size(64)
+    ///     .data_size(32)
+    ///     .outboard()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub const fn builder() -> SnowBinInfoBuilder {
+        SnowBinInfoBuilder::new()
     }
-}
 
-/// Allows writing to a `SnowBinary` file.
-#[derive(Debug)]
-pub struct SnowBinWriter {
-    info: SnowBinInfo,
-    file: File,
-    hasher: blake3::Hasher,
-    done: bool,
-}
+    /// Returns the max length of a header in bytes.
+    #[must_use]
+    pub const fn header_size(&self) -> u32 {
+        self.header_size
+    }
 
-impl SnowBinWriter {
-    /// Creates a new `SnowBinWriter` using the params of `SnowBinInfo`.
+    /// Returns the width of each entry's data length in bits.
+    #[must_use]
+    pub const fn data_size(&self) -> u8 {
+        self.data_size
+    }
+
+    /// Writes each header as a length followed by its bytes instead of padding it to `header_size`.
+    /// Headers then round-trip exactly, may end in spaces, and only take up the space they need, so
+    /// `header_size` can be set high without wasting space. This requires Spec 3.
     /// # Example
     /// ```
-    /// use std::path::PathBuf;
-    ///
-    /// use snowbinary::{SnowBinInfo, SnowBinWriter};
+    /// use snowbinary::SnowBinInfo;
     ///
-    /// let writer = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("file.temp"));
+    /// let info = SnowBinInfo::new(u32::MAX, 64).unwrap().with_variable_headers();
     /// ```
-    /// # Errors
-    /// Returns `SnowBinError` if the file could not be created or opened, or the file cannot be written to.
-    pub fn new(info: SnowBinInfo, path: PathBuf) -> Result<Self, SnowBinError> {
-        let Ok(mut file) = File::create(path)
-        else {
-            return Err(SnowBinError::CouldNotCreateOrOpenFile);
-        };
-
-        let mut hasher = blake3::Hasher::new();
-
-        Self::init_file(&mut file, &mut hasher, info)?;
+    #[must_use]
+    pub const fn with_variable_headers(mut self) -> Self {
+        self.flags |= FLAG_VARIABLE_HEADERS;
+        self
+    }
 
-        Ok(Self {
-            info,
-            file,
-            hasher,
-            done: false,
-        })
+    /// Returns true if headers are length prefixed instead of padded.
+    #[must_use]
+    pub const fn variable_headers(&self) -> bool {
+        self.flags & FLAG_VARIABLE_HEADERS != 0
     }
 
-    fn init_file(
-        file: &mut File,
-        hasher: &mut blake3::Hasher,
-        info: SnowBinInfo,
-    ) -> Result<(), SnowBinError> {
-        file.rewind().map_err(|_| SnowBinError::IOWriteError)?;
+    /// Writes each entry's data length as a LEB128 varint instead of a `data_size` wide uint.
+    /// Small entries then only use one byte for their length and any length up to `u64::MAX` can
+    /// be written, regardless of `data_size`. This requires Spec 3.
+    /// # Example
+    /// ```
+    /// use snowbinary::SnowBinInfo;
+    ///
+    /// let info = SnowBinInfo::default().with_varint_sizes();
+    /// ```
+    #[must_use]
+    pub const fn with_varint_sizes(mut self) -> Self {
+        self.flags |= FLAG_VARINT_SIZES;
+        self
+    }
 
-        writer::write_header(file, "SNOW_BIN", 8)?;
-        writer::write_u64(file, VERSION_SPEC)?;
-        writer::write_u32(file, info.header_size)?;
-        writer::write_u8(file, info.data_size)?;
+    /// Returns true if data lengths are written as varints.
+    #[must_use]
+    pub const fn varint_sizes(&self) -> bool {
+        self.flags & FLAG_VARINT_SIZES != 0
+    }
 
-        hasher.update(b"SNOW_BIN");
-        hasher.update(&VERSION_SPEC.to_le_bytes());
-        hasher.update(&info.header_size.to_le_bytes());
-        hasher.update(&info.data_size.to_le_bytes());
+    /// Stores a `SnowBinMetadata` attribute block with each entry, between its length and its data.
+    /// Attributes such as timestamps and content types can then be read without reading the data.
+    /// Entries written without metadata get an empty block. This requires Spec 3.
+    /// # Example
+    /// ```
+    /// use snowbinary::SnowBinInfo;
+    ///
+    /// let info = SnowBinInfo::default().with_entry_metadata();
+    /// ```
+    #[must_use]
+    pub const fn with_entry_metadata(mut self) -> Self {
+        self.flags |= FLAG_ENTRY_METADATA;
+        self
+    }
 
-        Ok(())
+    /// Returns true if entries carry a metadata block.
+    #[must_use]
+    pub const fn entry_metadata(&self) -> bool {
+        self.flags & FLAG_ENTRY_METADATA != 0
     }
 
-    /// Writes a header and some data to a `SnowBinary` file.
-    /// allowed, or the file could not be written to.
+    /// Stores a `SnowBinMetadata` attribute block for the whole file right after the file info,
+    /// such as when and by what the file was written. The block is covered by the hash.
+    /// Write it with `SnowBinWriter::with_metadata`. This requires Spec 3.
     /// # Example
     /// ```
-    /// use std::path::PathBuf;
-    ///
-    /// use snowbinary::{SnowBinError, SnowBinInfo, SnowBinWriter};
+    /// use snowbinary::SnowBinInfo;
     ///
-    /// let mut writer = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("file.temp"));
-    /// match &mut writer {
-    ///     Ok(writer) => {
-    ///         writer.write("Header", "This is data!".as_bytes()).unwrap();
-    ///     }
-    ///     Err(_) => {}
-    /// }
+    /// let info = SnowBinInfo::default().with_file_metadata();
     /// ```
-    /// # Errors
-    /// Returns `SnowBinError` if the header is too long, the data is too long, the data size is not
-    //TODO Should this check for headers of the same name?
-    pub fn write(&mut self, header: &str, data: &[u8]) -> Result<(), SnowBinError> {
-        if !self.done {
-            // Check
-            if header.len() > self.info.header_size as usize {
-                return Err(SnowBinError::HeaderTooLong);
-            }
-            let max = self.get_max_size()?;
-            if data.len() as u64 > max {
-                return Err(SnowBinError::DataTooLong);
-            }
-
-            // Write Data
-            let header = writer::write_header(&mut self.file, header, self.info.header_size)?;
-            self.hasher.update(&header);
-
-            #[allow(clippy::cast_possible_truncation)]
-            match self.info.data_size {
-                8 => {
-                    writer::write_u8(&mut self.file, data.len() as u8)?;
-                    self.hasher.update(&(data.len() as u8).to_le_bytes());
-                }
-                16 => {
-                    writer::write_u16(&mut self.file, data.len() as u16)?;
-                    self.hasher.update(&(data.len() as u16).to_le_bytes());
-                }
-                32 => {
-                    writer::write_u32(&mut self.file, data.len() as u32)?;
-                    self.hasher.update(&(data.len() as u32).to_le_bytes());
-                }
-                64 => {
-                    writer::write_u64(&mut self.file, data.len() as u64)?;
-                    self.hasher.update(&(data.len() as u64).to_le_bytes());
-                }
-                _ => return Err(SnowBinError::DataSizeNotAllowed),
-            }
-
-            writer::write_bytes(&mut self.file, data)?;
-            self.hasher.update(data);
-
-            return Ok(());
-        }
-
-        Err(SnowBinError::IOWriterClosed)
+    #[must_use]
+    pub const fn with_file_metadata(mut self) -> Self {
+        self.flags |= FLAG_FILE_METADATA;
+        self
     }
 
-    fn get_max_size(&self) -> Result<u64, SnowBinError> {
-        Ok(match self.info.data_size {
-            8 => u64::from(u8::MAX),
-            16 => u64::from(u16::MAX),
-            32 => u64::from(u32::MAX),
-            64 => u64::MAX,
-            _ => return Err(SnowBinError::DataSizeNotAllowed),
-        })
+    /// Returns true if the file has a metadata block.
+    #[must_use]
+    pub const fn file_metadata(&self) -> bool {
+        self.flags & FLAG_FILE_METADATA != 0
     }
 
-    /// Closes the writer. (Alt: you could drop the writer, but this could cause a panic)
+    /// Stores Bao-style outboard hashes after each entry's data: a blake3 chaining value for every
+    /// 16 KiB of data and the hash of the whole entry. Readers can then verify any range of an
+    /// entry by only hashing the 16 KiB groups it touches, see `SnowBinReader::read_range`.
+    /// This costs 32 bytes per 16 KiB of data. This requires Spec 3.
+    ///
+    /// Outboard hashes guard against accidental corruption, not tampering. Without full
+    /// verification nothing ties them to the hash at the end of the file, see
+    /// `SnowBinVerification::Skip`.
     /// # Example
     /// ```
-    /// use std::path::PathBuf;
-    ///
-    /// use snowbinary::{SnowBinError, SnowBinInfo, SnowBinWriter};
+    /// use snowbinary::SnowBinInfo;
     ///
-    /// let mut writer = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("file.temp"));
-    /// match &mut writer {
-    ///     Ok(writer) => {
-    ///         writer.write("Header", "This is data!".as_bytes()).unwrap();
-    ///         writer.close().unwrap(); // Or let the writer drop.
-    ///     }
-    ///     Err(_) => {}
-    /// }
+    /// let info = SnowBinInfo::default().with_outboard();
     /// ```
-    /// # Errors
-    /// Returns `SnowBinError` if the file cannot be written to or the writer was already closed.
-    pub fn close(&mut self) -> Result<(), SnowBinError> {
-        use std::io::Write;
-
-        if !self.done {
-            let header = writer::write_header(&mut self.file, "SNOW_END", self.info.header_size)?;
+    #[must_use]
+    pub const fn with_outboard(mut self) -> Self {
+        self.flags |= FLAG_OUTBOARD;
+        self
+    }
 
-            // Write hash
-            self.hasher.update(&header);
-            let hash = self.hasher.finalize();
-            let hash = hash.as_bytes();
-            writer::write_bytes(&mut self.file, hash)?;
+    /// Returns true if entries have outboard hashes.
+    #[must_use]
+    pub const fn outboard(&self) -> bool {
+        self.flags & FLAG_OUTBOARD != 0
+    }
 
-            self.file.flush().map_err(|_| SnowBinError::IOWriteError)?;
+    /// Stores each distinct payload once. An entry whose data was already written under another
+    /// header only stores a reference to it, which readers follow transparently. Payloads are
+    /// matched by their blake3 hash, so the writer keeps 40 bytes per distinct payload in memory.
+    /// This requires Spec 3.
+    /// # Example
+    /// ```
+    /// use snowbinary::SnowBinInfo;
+    ///
+    /// let info = SnowBinInfo::default().with_dedup();
+    /// ```
+    #[must_use]
+    pub const fn with_dedup(mut self) -> Self {
+        self.flags |= FLAG_DEDUP;
+        self
+    }
 
-            self.done = true;
+    /// Returns true if identical payloads are only stored once.
+    #[must_use]
+    pub const fn dedup(&self) -> bool {
+        self.flags & FLAG_DEDUP != 0
+    }
 
-            return Ok(());
+    /// Pads in front of each entry's data so that it starts at a multiple of `alignment` bytes from
+    /// the start of the file. If the file is loaded or mapped at an equally aligned address, payloads
+    /// can be cast to typed slices without copying. The padding is covered by the hash. An alignment
+    /// of 1 turns padding off, anything else requires Spec 3.
+    /// # Example
+    /// ```
+    /// use snowbinary::SnowBinInfo;
+    ///
+    /// let info = SnowBinInfo::default().with_alignment(64).unwrap();
+    /// ```
+    /// # Errors
+    /// Returns `SnowBinError` if `alignment` is not a power of two or is over 65536.
+    pub const fn with_alignment(mut self, alignment: u32) -> Result<Self, SnowBinError> {
+        if !alignment.is_power_of_two() || alignment > MAX_ALIGNMENT {
+            return Err(SnowBinError::AlignmentNotAllowed);
         }
 
-        Err(SnowBinError::IOWriterClosed)
+        self.flags =
+            (self.flags & !ALIGNMENT_MASK) | (alignment.trailing_zeros() << ALIGNMENT_SHIFT);
+        Ok(self)
     }
-}
 
-impl Drop for SnowBinWriter {
-    fn drop(&mut self) {
-        if !self.done {
-            self.close()
-                .expect("Could not properly drop SnowBinWriter.");
-        }
+    /// Returns the alignment of each entry's data in bytes.
+    #[must_use]
+    pub const fn alignment(&self) -> u32 {
+        1 << ((self.flags & ALIGNMENT_MASK) >> ALIGNMENT_SHIFT)
     }
-}
-
-/// Allows reading from a `SnowBinary` file.
-#[derive(Debug)]
-pub struct SnowBinReader {
-    info: SnowBinInfo,
-    file: File,
-}
 
-//TODO: Allow dumping of headers.
-impl SnowBinReader {
-    /// Creates a new `SnowBinReader`. Params are pulled from the file info.
+    /// Treats headers as paths split by `separator`, such as `textures/ui/button`.
+    /// Headers written with a separator must not have empty components, and readers use the
+    /// separator to list the children of a path and walk a subtree. This requires Spec 3.
     /// # Example
     /// ```
-    /// use std::path::PathBuf;
-    ///
-    /// use snowbinary::{SnowBinInfo, SnowBinReader};
+    /// use snowbinary::SnowBinInfo;
     ///
-    /// let reader = SnowBinReader::new(PathBuf::from("file.temp")).unwrap();
+    /// let info = SnowBinInfo::new(64, 64)
+    ///     .unwrap()
+    ///     .with_separator('/')
+    ///     .unwrap();
     /// ```
     /// # Errors
-    /// Returns `SnowBinError` if the file could not be created or opened, or the file cannot be read from.
-    pub fn new(path: PathBuf) -> Result<Self, SnowBinError> {
-        let Ok(mut file) = File::open(path)
-        else {
-            return Err(SnowBinError::CouldNotCreateOrOpenFile);
-        };
-
-        let info = Self::read_info(&mut file)?;
+    /// Returns `SnowBinError` if `separator` is not ASCII punctuation.
+    pub const fn with_separator(mut self, separator: char) -> Result<Self, SnowBinError> {
+        if !separator.is_ascii_punctuation() {
+            return Err(SnowBinError::SeparatorNotAllowed);
+        }
 
-        Ok(Self { info, file })
+        self.flags = (self.flags & !SEPARATOR_MASK) | ((separator as u32) << SEPARATOR_SHIFT);
+        Ok(self)
     }
 
-    fn read_info(file: &mut File) -> Result<SnowBinInfo, SnowBinError> {
-        use std::io::Read;
-
-        // Check hash
-        let hash = {
-            file.rewind().map_err(|_| SnowBinError::IOReadError)?;
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer)
-                .map_err(|_| SnowBinError::IOReadError)?;
-            buffer.drain((buffer.len() - HASH_SIZE as usize)..buffer.len());
-
-            blake3::hash(&buffer)
-        };
-        let hash = hash.as_bytes();
-
-        {
-            file.seek(SeekFrom::End(-(i64::from(HASH_SIZE))))
-                .map_err(|_| SnowBinError::IOReadError)?;
-            let read_hash = reader::read_bytes(file, u64::from(HASH_SIZE))?;
-
-            if !read_hash.eq(hash) {
-                return Err(SnowBinError::HashDoesNotMatch);
-            }
+    /// Returns the path separator, if headers are paths.
+    #[must_use]
+    pub const fn separator(&self) -> Option<char> {
+        #[allow(clippy::cast_possible_truncation)]
+        match ((self.flags & SEPARATOR_MASK) >> SEPARATOR_SHIFT) as u8 {
+            0 => None,
+            separator => Some(separator as char),
         }
+    }
 
-        // Read file config
-        file.rewind().map_err(|_| SnowBinError::IOReadError)?;
-
-        let snow_header = reader::read_header(file, 8)?;
-        if !snow_header.eq("SNOW_BIN") {
-            return Err(SnowBinError::MalformedHeader);
+    const fn version(&self) -> u64 {
+        if self.flags == 0 {
+            MIN_VERSION_SPEC
         }
-
-        let version = reader::read_u64(file)?;
-        if version != VERSION_SPEC {
-            return Err(SnowBinError::WrongSpecVersion);
+        else {
+            VERSION_SPEC
         }
-
-        let header_size = reader::read_u32(file)?;
-
-        let data_size = reader::read_u8(file)?;
-        match data_size {
-            8 | 16 | 32 | 64 => (),
-            _ => return Err(SnowBinError::DataSizeNotAllowed),
-        };
-
-        Ok(SnowBinInfo {
-            header_size,
-            data_size,
-        })
     }
 
-    /// Reads data from the file using the header.
-    /// # Example
-    /// ```
-    /// use std::path::PathBuf;
-    ///
-    /// use snowbinary::{SnowBinError, SnowBinInfo, SnowBinReader};
-    ///
-    /// let mut reader = SnowBinReader::new(PathBuf::from("file.temp"));
-    /// match &mut reader {
-    ///     Ok(reader) => {
-    ///         let data = reader.read("Header"); // May return error
-    ///     }
-    ///     Err(_) => {}
-    /// }
-    /// ```
-    /// # Errors
-    /// Returns `SnowBinError` if the file cannot be read from or the end of the file was reached.
-    /// # Panics
-    /// Panics **could** happen if data size is over `i64::MAX`.
-    pub fn read(&mut self, header: &str) -> Result<Vec<u8>, SnowBinError> {
-        self.file
-            .seek(SeekFrom::Start(DATA_START))
-            .map_err(|_| SnowBinError::IOReadError)?;
-
-        let mut buffer = vec![32_u8; self.info.header_size as usize];
-        buffer.splice(0..header.len(), header.as_bytes().iter().copied());
-        let header = String::from_utf8(buffer).map_err(|_| SnowBinError::MalformedHeader)?;
-
-        let mut f_header = String::new();
-        let mut data = Vec::new();
-        let mut store = 0_u64;
-        while !f_header.eq(&header) {
-            f_header = reader::read_header(&mut self.file, self.info.header_size)?;
-
-            if f_header.starts_with("SNOW_END") {
-                return Err(SnowBinError::ReachedEOF);
-            }
-
-            let size = match self.info.data_size {
-                8 => u64::from(reader::read_u8(&mut self.file)?),
-                16 => u64::from(reader::read_u16(&mut self.file)?),
-                32 => u64::from(reader::read_u32(&mut self.file)?),
-                64 => reader::read_u64(&mut self.file)?,
-                _ => return Err(SnowBinError::DataSizeNotAllowed),
-            };
-
-            if f_header.eq(&header) {
-                data = reader::read_bytes(&mut self.file, size)?;
-            }
-            else {
-                let mut file = &self.file;
-
-                let tmp = if size > i64::MAX as u64 {
-                    let s = size - i64::MAX as u64;
-                    file.seek(SeekFrom::Current(i64::MAX))
-                        .map_err(|_| SnowBinError::IOReadError)?;
-                    file.seek(SeekFrom::Current(s.try_into().unwrap()))
-                        .map_err(|_| SnowBinError::IOReadError)?
-                }
-                else {
-                    file.seek(SeekFrom::Current(size.try_into().unwrap()))
-                        .map_err(|_| SnowBinError::IOReadError)?
-                };
-
-                if tmp == store {
-                    return Err(SnowBinError::ReachedEOF);
-                }
-                store = tmp;
-            }
+    const fn data_start(&self) -> u64 {
+        if self.flags == 0 {
+            DATA_START
         }
+        else {
+            DATA_START_V3
+        }
+    }
+}
 
-        Ok(data)
+impl Default for SnowBinInfo {
+    fn default() -> Self {
+        Self {
+            header_size: DEFAULT_HEADER_SIZE,
+            data_size: DATA_SIZES[DEFAULT_DATA_SIZE],
+            flags: 0,
+        }
     }
 }
@@ -0,0 +1,108 @@
+use core::fmt;
+
+/// Resource limits applied while reading a `SnowBinary` file.
+///
+/// Limits are checked before anything is allocated, so they can be used to safely read untrusted files.
+/// Default returns `SnowBinLimits` with no limits.
+/// # Example
+/// ```
+/// use snowbinary::SnowBinLimits;
+///
+/// let limits = SnowBinLimits::new()
+///     .max_header_size(64)
+///     .max_entry_size(1024 * 1024)
+///     .max_entries(1000)
+///     .max_total_bytes(16 * 1024 * 1024);
+/// ```
+#[allow(clippy::struct_field_names)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SnowBinLimits {
+    pub(crate) max_header_size: u32,
+    pub(crate) max_entry_size: u64,
+    pub(crate) max_entries: u64,
+    pub(crate) max_total_bytes: u64,
+    pub(crate) max_metadata_size: u64,
+}
+
+impl SnowBinLimits {
+    /// Creates a new `SnowBinLimits` with no limits.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_header_size: u32::MAX,
+            max_entry_size: u64::MAX,
+            max_entries: u64::MAX,
+            max_total_bytes: u64::MAX,
+            max_metadata_size: u64::MAX,
+        }
+    }
+
+    /// Sets the largest header size the file info may declare.
+    #[must_use]
+    pub const fn max_header_size(mut self, max: u32) -> Self {
+        self.max_header_size = max;
+        self
+    }
+
+    /// Sets the largest payload a single entry may have.
+    #[must_use]
+    pub const fn max_entry_size(mut self, max: u64) -> Self {
+        self.max_entry_size = max;
+        self
+    }
+
+    /// Sets how many entries may be walked over while looking for a header.
+    #[must_use]
+    pub const fn max_entries(mut self, max: u64) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    /// Sets the largest total size of the file.
+    #[must_use]
+    pub const fn max_total_bytes(mut self, max: u64) -> Self {
+        self.max_total_bytes = max;
+        self
+    }
+
+    /// Sets the largest metadata block a single entry may have.
+    #[must_use]
+    pub const fn max_metadata_size(mut self, max: u64) -> Self {
+        self.max_metadata_size = max;
+        self
+    }
+}
+
+impl Default for SnowBinLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The limit in `SnowBinLimits` that was exceeded.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum SnowBinLimit {
+    /// `SnowBinLimits::max_header_size`.
+    HeaderSize,
+    /// `SnowBinLimits::max_entry_size`.
+    EntrySize,
+    /// `SnowBinLimits::max_entries`.
+    Entries,
+    /// `SnowBinLimits::max_total_bytes`.
+    TotalBytes,
+    /// `SnowBinLimits::max_metadata_size`.
+    MetadataSize,
+}
+
+impl fmt::Display for SnowBinLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HeaderSize => write!(f, "header size"),
+            Self::EntrySize => write!(f, "entry size"),
+            Self::Entries => write!(f, "entries"),
+            Self::TotalBytes => write!(f, "total bytes"),
+            Self::MetadataSize => write!(f, "metadata size"),
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::SnowBinError,
+    file::{SnowBinDropAction, SnowBinReader},
+    format::{self, Entry},
+    journal::{self, Change, Journal},
+    options::SnowBinOpenOptions,
+    summary::SnowBinWriteSummary,
+    SnowBinInfo,
+};
+
+/// A value of a `SnowBinMap`, either still in the file or changed since the last flush.
+#[derive(Debug)]
+enum Value {
+    Stored(Entry),
+    Changed(Vec<u8>),
+}
+
+/// A persistent map from headers to data, backed by a `SnowBinary` file.
+///
+/// The headers of the file are indexed when it is opened, so `get` goes straight to the entry
+/// instead of walking the file. Inserts and removals are kept in memory until `flush`, which
+/// rewrites the file through a `SnowBinWriter` with atomic writes. The trailing hash covers the
+/// whole file, so it cannot be appended to in place, but unchanged data is copied over from the old
+/// file without being held in memory all at once. Readers never see a partially written file, and
+/// the new file is verified like any other.
+///
+/// Keys are kept in sorted order. If the file uses a header more than once, only its first entry is
+/// kept, like `SnowBinReader::read` does. Changes that were not flushed are lost when the map is
+/// dropped, unless it is journaled, see `SnowBinOpenOptions::journal`.
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use snowbinary::{SnowBinInfo, SnowBinMap};
+///
+/// let mut map = SnowBinMap::create(SnowBinInfo::default(), PathBuf::from("map.temp")).unwrap();
+/// map.insert("Header", b"This is data!").unwrap();
+/// map.insert("Other", b"More data").unwrap();
+/// map.flush().unwrap();
+///
+/// let mut map = SnowBinMap::open(PathBuf::from("map.temp")).unwrap();
+/// assert_eq!(map.get("Header").unwrap().unwrap(), b"This is data!");
+/// assert!(map.remove("Other").unwrap());
+/// assert_eq!(map.keys().collect::<Vec<_>>(), ["Header"]);
+/// map.flush().unwrap();
+/// # std::fs::remove_file("map.temp").unwrap();
+/// ```
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug)]
+pub struct SnowBinMap {
+    path: PathBuf,
+    options: SnowBinOpenOptions,
+    info: SnowBinInfo,
+    /// Only `None` if a flush closed the file and could not reopen it, it is reopened on the next
+    /// call.
+    reader: Option<SnowBinReader>,
+    index: BTreeMap<String, Value>,
+    journal: Option<Journal>,
+    dirty: bool,
+    /// Set if a flush replaced the file but could not reopen the map from it, which is done on the
+    /// next call instead. The index still has the same keys and data as the new file.
+    stale: bool,
+}
+
+impl SnowBinMap {
+    /// Opens the `SnowBinary` file at `path` as a map with default options.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be opened, the file cannot be read from, or the
+    /// hash does not match.
+    pub fn open(path: PathBuf) -> Result<Self, SnowBinError> {
+        SnowBinOpenOptions::new().open_map(path)
+    }
+
+    /// Creates an empty `SnowBinary` file at `path` with `info`, and opens it as a map with default
+    /// options. Any existing file at `path` is replaced.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be created, written to, or read back.
+    pub fn create(info: SnowBinInfo, path: PathBuf) -> Result<Self, SnowBinError> {
+        SnowBinOpenOptions::new().create_map(info, path)
+    }
+
+    pub(crate) fn open_with(
+        path: PathBuf,
+        options: SnowBinOpenOptions,
+    ) -> Result<Self, SnowBinError> {
+        let mut reader = options.open(path.clone())?;
+        let index = Self::index(&mut reader)?;
+
+        let base = journal::file_hash(&path)?;
+        let (changes, end) = journal::recover(&path, base)?;
+        let mut map = Self {
+            journal: options.journal.then(|| Journal::new(&path, base, end)),
+            path,
+            options,
+            info: reader.info(),
+            reader: Some(reader),
+            index,
+            dirty: false,
+            stale: false,
+        };
+        if changes.is_empty() {
+            // A journal that did not match the file or had no complete records is discarded.
+            journal::remove(&map.path)?;
+            return Ok(map);
+        }
+
+        for change in changes {
+            match change {
+                Change::Insert(key, data) => {
+                    map.index.insert(key, Value::Changed(data));
+                }
+                Change::Remove(key) => {
+                    map.index.remove(&key);
+                }
+            }
+        }
+        // The changes stay in the journal until the map is flushed.
+        map.dirty = true;
+
+        Ok(map)
+    }
+
+    pub(crate) fn create_with(
+        info: SnowBinInfo,
+        path: PathBuf,
+        options: SnowBinOpenOptions,
+    ) -> Result<Self, SnowBinError> {
+        options.atomic(true).create(info, path.clone())?.finish()?;
+        // A journal left over from an older file must not be replayed onto the new one.
+        journal::remove(&path)?;
+        Self::open_with(path, options)
+    }
+
+    /// Indexes the first entry of each header.
+    fn index(reader: &mut SnowBinReader) -> Result<BTreeMap<String, Value>, SnowBinError> {
+        let info = reader.info();
+        let mut index = BTreeMap::new();
+        for entry in reader.entries()? {
+            index
+                .entry(String::from(entry.name(info)))
+                .or_insert(Value::Stored(entry));
+        }
+
+        Ok(index)
+    }
+
+    /// Returns the path of the file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the file info the file is written with.
+    #[must_use]
+    pub const fn info(&self) -> SnowBinInfo {
+        self.info
+    }
+
+    /// Returns the data of `key`, or `None` if the map does not have it.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, or the data does not match its
+    /// outboard hashes.
+    pub fn get(&mut self, key: &str) -> Result<Option<Vec<u8>>, SnowBinError> {
+        self.reopen_if_stale()?;
+        match self.index.get(key) {
+            None => Ok(None),
+            Some(Value::Changed(data)) => Ok(Some(data.clone())),
+            Some(Value::Stored(entry)) => reader(&mut self.reader, &self.path, self.options)?
+                .read_entry(entry)
+                .map(Some)
+                .map_err(|e| e.with_header(key)),
+        }
+    }
+
+    /// Returns true if the map has `key`.
+    #[must_use]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// Iterates over the keys in sorted order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(String::as_str)
+    }
+
+    /// Returns the number of keys.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns true if the map has no keys.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Returns true if the map was changed since it was opened or last flushed.
+    #[must_use]
+    pub const fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Sets the data of `key`, replacing any data it had. Nothing is written until `flush`.
+    /// # Errors
+    /// Returns `SnowBinError` if `key` or `data` cannot be written with the file info of the map,
+    /// such as a key longer than the header size.
+    pub fn insert(&mut self, key: &str, data: &[u8]) -> Result<(), SnowBinError> {
+        format::check_entry(self.info(), key, data.len() as u64)?;
+        self.reopen_if_stale()?;
+        if let Some(journal) = &mut self.journal {
+            journal.insert(key, data)?;
+        }
+        self.index
+            .insert(String::from(key), Value::Changed(data.to_vec()));
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Removes `key`, returning true if the map had it. Nothing is written until `flush`.
+    /// # Errors
+    /// Returns `SnowBinError` if the map is journaled and the journal could not be written to.
+    pub fn remove(&mut self, key: &str) -> Result<bool, SnowBinError> {
+        if !self.index.contains_key(key) {
+            return Ok(false);
+        }
+
+        self.reopen_if_stale()?;
+        if let Some(journal) = &mut self.journal {
+            journal.remove(key)?;
+        }
+        self.index.remove(key);
+        self.dirty = true;
+        Ok(true)
+    }
+
+    /// Writes the map to its file if it was changed, and reopens the file. Entries that were not
+    /// changed keep their metadata, and the file keeps its file info and file metadata.
+    /// Returns a summary of what was written, or `None` if nothing was changed.
+    /// # Errors
+    /// Returns `SnowBinError` if the old file cannot be read from, or the new file could not be
+    /// written or read back. If writing failed, the file is left as it was. If the new file was
+    /// written but could not be read back, the map is reopened from it on the next call.
+    pub fn flush(&mut self) -> Result<Option<SnowBinWriteSummary>, SnowBinError> {
+        self.reopen_if_stale()?;
+        if !self.dirty {
+            return Ok(None);
+        }
+
+        let reader = reader(&mut self.reader, &self.path, self.options)?;
+        let metadata = reader.file_metadata().clone();
+        // The temporary file is removed if anything fails, so the old file stays in place.
+        let mut writer = self
+            .options
+            .atomic(true)
+            .on_drop(SnowBinDropAction::Remove)
+            .create_with_metadata(self.info, self.path.clone(), &metadata)?;
+        for (key, value) in &self.index {
+            match value {
+                Value::Changed(data) => writer.write(key, data)?,
+                Value::Stored(entry) => {
+                    let data = reader.read_entry(entry).map_err(|e| e.with_header(key))?;
+                    let metadata = reader
+                        .entry_metadata(entry)
+                        .map_err(|e| e.with_header(key))?;
+                    writer.write_with_metadata(key, &data, &metadata)?;
+                }
+            }
+        }
+
+        // The old file is closed before the new one is moved over it, which Windows requires.
+        self.reader = None;
+        let summary = match writer.finish() {
+            Ok(summary) => summary,
+            Err(e) => {
+                // The old file is still in place, so the map can keep reading from it. If it cannot
+                // be reopened now, it is on the next call.
+                self.reader = self.options.open(self.path.clone()).ok();
+                return Err(e);
+            }
+        };
+
+        // The new file is in place, so the map is reopened from it even if the rest fails.
+        self.dirty = false;
+        self.stale = true;
+        // The journal no longer matches the file, so it would be discarded even if this failed.
+        journal::remove(&self.path)?;
+        self.reopen_if_stale()?;
+        Ok(Some(summary))
+    }
+
+    /// Reopens the map from its file if a flush replaced the file but could not reopen it.
+    fn reopen_if_stale(&mut self) -> Result<(), SnowBinError> {
+        if self.stale {
+            *self = Self::open_with(self.path.clone(), self.options)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the reader of the file at `path`, reopening it if a flush closed it.
+fn reader<'a>(
+    reader: &'a mut Option<SnowBinReader>,
+    path: &Path,
+    options: SnowBinOpenOptions,
+) -> Result<&'a mut SnowBinReader, SnowBinError> {
+    let open = match reader.take() {
+        Some(open) => open,
+        None => options.open(path.to_path_buf())?,
+    };
+    Ok(reader.insert(open))
+}
@@ -0,0 +1,263 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    diff::{self, SnowBinDiff},
+    error::SnowBinError,
+    format::{self, Encoder, Walker},
+    limits::SnowBinLimits,
+    metadata::SnowBinMetadata,
+    nested::SnowBinNestedWriter,
+    path,
+    reader::SliceInput,
+    schema::{self, SnowBinSchema, SnowBinViolation},
+    SnowBinInfo,
+};
+
+/// Encodes a `SnowBinary` file into memory. Works without `std`.
+/// # Example
+/// ```
+/// use snowbinary::{SnowBinDecoder, SnowBinEncoder, SnowBinInfo};
+///
+/// let mut encoder = SnowBinEncoder::new(SnowBinInfo::default()).unwrap();
+/// encoder.write("Header", b"This is data!").unwrap();
+/// let bytes = encoder.finish().unwrap();
+///
+/// let decoder = SnowBinDecoder::new(&bytes).unwrap();
+/// assert_eq!(decoder.read("Header").unwrap(), b"This is data!");
+/// ```
+#[derive(Debug)]
+pub struct SnowBinEncoder {
+    encoder: Encoder<Vec<u8>>,
+}
+
+impl SnowBinEncoder {
+    /// Creates a new `SnowBinEncoder` using the params of `SnowBinInfo`.
+    /// # Errors
+    /// Returns `SnowBinError` if the file info could not be encoded.
+    pub fn new(info: SnowBinInfo) -> Result<Self, SnowBinError> {
+        Self::with_metadata(info, &SnowBinMetadata::new())
+    }
+
+    /// Creates a new `SnowBinEncoder` that stores `metadata` for the whole file.
+    /// # Errors
+    /// Returns `SnowBinError` if the file info could not be encoded, or `info` was not created with
+    /// `SnowBinInfo::with_file_metadata`.
+    pub fn with_metadata(
+        info: SnowBinInfo,
+        metadata: &SnowBinMetadata,
+    ) -> Result<Self, SnowBinError> {
+        Ok(Self {
+            encoder: Encoder::new(info, Vec::new(), metadata)?,
+        })
+    }
+
+    /// Writes a header and some data.
+    /// # Errors
+    /// Returns `SnowBinError` if the header is too long, the data is too long, or the data size is not
+    /// allowed.
+    pub fn write(&mut self, header: &str, data: &[u8]) -> Result<(), SnowBinError> {
+        self.encoder.write(header, data, &SnowBinMetadata::new())
+    }
+
+    /// Writes a header, some data, and its metadata.
+    /// # Errors
+    /// Returns `SnowBinError` if `write` would, or the encoder was not created with
+    /// `SnowBinInfo::with_entry_metadata`.
+    pub fn write_with_metadata(
+        &mut self,
+        header: &str,
+        data: &[u8],
+        metadata: &SnowBinMetadata,
+    ) -> Result<(), SnowBinError> {
+        self.encoder.write(header, data, metadata)
+    }
+
+    /// Starts writing another `SnowBinary` file directly into an entry with `header`.
+    /// The entry is finished when the nested writer is closed.
+    /// # Errors
+    /// Returns `SnowBinError` if the header is too long or the encoder was finished.
+    pub fn write_nested(
+        &mut self,
+        header: &str,
+        info: SnowBinInfo,
+    ) -> Result<SnowBinNestedWriter<'_>, SnowBinError> {
+        SnowBinNestedWriter::new(&mut self.encoder, header, info)
+    }
+
+    /// Writes the end header and hash, and returns the encoded bytes.
+    /// # Errors
+    /// Returns `SnowBinError` if the end header could not be encoded.
+    pub fn finish(mut self) -> Result<Vec<u8>, SnowBinError> {
+        self.encoder.close()?;
+        Ok(self.encoder.into_output())
+    }
+}
+
+/// Decodes a `SnowBinary` file from a byte slice without copying payloads. Works without `std`.
+#[derive(Copy, Clone, Debug)]
+pub struct SnowBinDecoder<'a> {
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+    data: &'a [u8],
+}
+
+impl<'a> SnowBinDecoder<'a> {
+    /// Creates a new `SnowBinDecoder`, checking the hash. Params are pulled from the file info.
+    /// # Errors
+    /// Returns `SnowBinError` if the data is truncated, malformed, or does not match its hash.
+    pub fn new(data: &'a [u8]) -> Result<Self, SnowBinError> {
+        Self::with_limits(data, SnowBinLimits::default())
+    }
+
+    /// Creates a new `SnowBinDecoder` that enforces `limits` while reading.
+    /// # Errors
+    /// Returns `SnowBinError` if the data is truncated, malformed, does not match its hash, or
+    /// exceeds `limits`.
+    pub fn with_limits(data: &'a [u8], limits: SnowBinLimits) -> Result<Self, SnowBinError> {
+        format::verify_slice(data, limits)?;
+        let mut input = SliceInput::new(data);
+        let info = format::read_info(&mut input, limits)?;
+
+        Ok(Self { info, limits, data })
+    }
+
+    /// Reads data using the header, borrowing it from the underlying slice.
+    /// # Errors
+    /// Returns `SnowBinError` if the data is truncated, the end of the data was reached, or an
+    /// entry exceeds the decoder's limits.
+    pub fn read(&self, header: &str) -> Result<&'a [u8], SnowBinError> {
+        let mut input = SliceInput::new(self.data);
+        let size = format::find(&mut input, self.info, self.limits, header)?;
+        input.take(size)
+    }
+
+    /// Reads the metadata stored for the whole file.
+    /// Files without file metadata have empty metadata.
+    /// # Errors
+    /// Returns `SnowBinError` if the metadata is malformed or exceeds the decoder's limits.
+    pub fn file_metadata(&self) -> Result<SnowBinMetadata, SnowBinError> {
+        let mut input = SliceInput::new(self.data);
+        format::read_file_metadata(&mut input, self.info, self.limits)
+    }
+
+    /// Reads the metadata of an entry without reading its data.
+    /// Entries of files without entry metadata have empty metadata.
+    /// # Errors
+    /// Returns `SnowBinError` if the data is truncated, the end of the data was reached, the
+    /// metadata is malformed, or an entry exceeds the decoder's limits.
+    pub fn metadata(&self, header: &str) -> Result<SnowBinMetadata, SnowBinError> {
+        let mut input = SliceInput::new(self.data);
+        format::metadata(&mut input, self.info, self.limits, header)
+    }
+
+    /// Opens the entry with `header` as a `SnowBinary` file of its own, such as one written with
+    /// `SnowBinEncoder::write_nested`. The nested decoder uses the same limits as this one.
+    /// # Errors
+    /// Returns `SnowBinError` if the end of the data was reached or the entry is not a valid
+    /// `SnowBinary` file.
+    pub fn open_nested(&self, header: &str) -> Result<Self, SnowBinError> {
+        Self::with_limits(self.read(header)?, self.limits)
+    }
+
+    /// Lists every header, in the order they were written.
+    /// Padding is removed from fixed size headers.
+    /// # Errors
+    /// Returns `SnowBinError` if the data is truncated or an entry exceeds the decoder's limits.
+    pub fn headers(&self) -> Result<Vec<String>, SnowBinError> {
+        format::headers(&mut SliceInput::new(self.data), self.info, self.limits)
+    }
+
+    /// Lists the paths of the direct children of `prefix`, in the order they first appear.
+    /// Paths are split by the file's separator, or `/` if it does not set one, and an empty prefix
+    /// lists the top level.
+    /// # Errors
+    /// Returns `SnowBinError` if the data is truncated or an entry exceeds the decoder's limits.
+    pub fn children(&self, prefix: &str) -> Result<Vec<String>, SnowBinError> {
+        format::children(
+            &mut SliceInput::new(self.data),
+            self.info,
+            self.limits,
+            prefix,
+        )
+    }
+
+    /// Compares the entries of this file, as the old one, with those of `new`. See `SnowBinDiff`.
+    /// # Errors
+    /// Returns `SnowBinError` if either file is truncated or an entry exceeds its decoder's limits.
+    pub fn diff(&self, new: &SnowBinDecoder<'_>) -> Result<SnowBinDiff, SnowBinError> {
+        diff::diff(
+            &mut SliceInput::new(self.data),
+            self.info,
+            self.limits,
+            &mut SliceInput::new(new.data),
+            new.info,
+            new.limits,
+        )
+    }
+
+    /// Checks every entry against `schema`, returning every violation. See `SnowBinSchema`.
+    /// # Errors
+    /// Returns `SnowBinError` if the data is truncated or an entry exceeds the limits.
+    pub fn validate(&self, schema: &SnowBinSchema) -> Result<Vec<SnowBinViolation>, SnowBinError> {
+        schema::validate(
+            &mut SliceInput::new(self.data),
+            self.info,
+            self.limits,
+            schema,
+        )
+    }
+
+    /// Iterates over the headers and data of `prefix` and every entry nested under it.
+    /// # Errors
+    /// Returns `SnowBinError` if the data is truncated or the file metadata exceeds the limits.
+    pub fn subtree(&self, prefix: &'a str) -> Result<SnowBinDecoderSubtree<'a>, SnowBinError> {
+        let mut input = SliceInput::new(self.data);
+        let walker = Walker::new(&mut input, self.info, self.limits)?;
+
+        Ok(SnowBinDecoderSubtree {
+            decoder: *self,
+            input,
+            prefix,
+            walker: Some(walker),
+        })
+    }
+}
+
+/// Iterator over the entries under a path of a `SnowBinDecoder`, returned by
+/// `SnowBinDecoder::subtree`.
+#[derive(Clone, Debug)]
+pub struct SnowBinDecoderSubtree<'a> {
+    decoder: SnowBinDecoder<'a>,
+    input: SliceInput<'a>,
+    prefix: &'a str,
+    walker: Option<Walker>,
+}
+
+impl<'a> Iterator for SnowBinDecoderSubtree<'a> {
+    type Item = Result<(String, &'a [u8]), SnowBinError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let info = self.decoder.info;
+        let separator = format::separator(info);
+
+        loop {
+            let entry = match self.walker.as_mut()?.next(&mut self.input) {
+                Ok(Some(entry)) => entry,
+                Ok(None) => {
+                    self.walker = None;
+                    return None;
+                }
+                Err(e) => {
+                    self.walker = None;
+                    return Some(Err(e));
+                }
+            };
+
+            let name = entry.name(info);
+            if path::in_subtree(name, self.prefix, separator) {
+                let data = &self.decoder.data[entry.range()];
+                return Some(Ok((String::from(name), data)));
+            }
+        }
+    }
+}
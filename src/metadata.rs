@@ -0,0 +1,219 @@
+use alloc::{
+    collections::{btree_map, BTreeMap},
+    string::String,
+    vec::Vec,
+};
+
+use crate::{
+    error::SnowBinError,
+    reader::{self, SliceInput},
+    writer,
+};
+
+/// A small set of attributes attached to an entry or a whole file, readable without reading any
+/// entry's data.
+///
+/// Keys are strings and values are bytes. Helpers are provided for a few common attributes.
+/// # Example
+/// ```
+/// use snowbinary::SnowBinMetadata;
+///
+/// let metadata = SnowBinMetadata::new()
+///     .with_modified(1_700_000_000)
+///     .with_content_type("image/png")
+///     .with("source", "assets/button.png");
+///
+/// assert_eq!(metadata.content_type(), Some("image/png"));
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct SnowBinMetadata {
+    attributes: BTreeMap<String, Vec<u8>>,
+}
+
+impl SnowBinMetadata {
+    /// Key of the modification time, in seconds since the Unix epoch as a little endian u64.
+    pub const MODIFIED: &'static str = "modified";
+    /// Key of the MIME type of the data.
+    pub const CONTENT_TYPE: &'static str = "content-type";
+    /// Key of the creation time, in seconds since the Unix epoch as a little endian u64.
+    pub const CREATED: &'static str = "created";
+    /// Key of the name of the application that wrote the file.
+    pub const APPLICATION: &'static str = "application";
+    /// Key of the version of the application that wrote the file.
+    pub const APPLICATION_VERSION: &'static str = "application-version";
+    /// Key of a free-form description.
+    pub const DESCRIPTION: &'static str = "description";
+
+    /// Creates a new empty `SnowBinMetadata`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// Adds an attribute, replacing any with the same key.
+    #[must_use]
+    pub fn with(mut self, key: &str, value: impl Into<Vec<u8>>) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    /// Adds an attribute, returning the old value if there was one with the same key.
+    pub fn insert(&mut self, key: &str, value: impl Into<Vec<u8>>) -> Option<Vec<u8>> {
+        self.attributes.insert(String::from(key), value.into())
+    }
+
+    /// Removes an attribute, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.attributes.remove(key)
+    }
+
+    /// Returns the value of an attribute.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.attributes.get(key).map(Vec::as_slice)
+    }
+
+    /// Returns the number of attributes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    /// Returns true if there are no attributes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    /// Iterates over the attributes, ordered by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_slice()))
+    }
+
+    /// Sets the modification time, in seconds since the Unix epoch.
+    #[must_use]
+    pub fn with_modified(self, seconds: u64) -> Self {
+        self.with(Self::MODIFIED, seconds.to_le_bytes())
+    }
+
+    /// Returns the modification time, in seconds since the Unix epoch.
+    #[must_use]
+    pub fn modified(&self) -> Option<u64> {
+        let bytes = self.get(Self::MODIFIED)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    /// Sets the MIME type of the data.
+    #[must_use]
+    pub fn with_content_type(self, content_type: &str) -> Self {
+        self.with(Self::CONTENT_TYPE, content_type)
+    }
+
+    /// Returns the MIME type of the data.
+    #[must_use]
+    pub fn content_type(&self) -> Option<&str> {
+        core::str::from_utf8(self.get(Self::CONTENT_TYPE)?).ok()
+    }
+
+    /// Sets the creation time, in seconds since the Unix epoch.
+    #[must_use]
+    pub fn with_created(self, seconds: u64) -> Self {
+        self.with(Self::CREATED, seconds.to_le_bytes())
+    }
+
+    /// Sets the creation time to now.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn with_created_now(self) -> Self {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        self.with_created(seconds)
+    }
+
+    /// Returns the creation time, in seconds since the Unix epoch.
+    #[must_use]
+    pub fn created(&self) -> Option<u64> {
+        let bytes = self.get(Self::CREATED)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    /// Sets the name and version of the application that wrote the file.
+    #[must_use]
+    pub fn with_application(self, name: &str, version: &str) -> Self {
+        self.with(Self::APPLICATION, name)
+            .with(Self::APPLICATION_VERSION, version)
+    }
+
+    /// Returns the name of the application that wrote the file.
+    #[must_use]
+    pub fn application(&self) -> Option<&str> {
+        core::str::from_utf8(self.get(Self::APPLICATION)?).ok()
+    }
+
+    /// Returns the version of the application that wrote the file.
+    #[must_use]
+    pub fn application_version(&self) -> Option<&str> {
+        core::str::from_utf8(self.get(Self::APPLICATION_VERSION)?).ok()
+    }
+
+    /// Sets a free-form description.
+    #[must_use]
+    pub fn with_description(self, description: &str) -> Self {
+        self.with(Self::DESCRIPTION, description)
+    }
+
+    /// Returns the free-form description.
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        core::str::from_utf8(self.get(Self::DESCRIPTION)?).ok()
+    }
+
+    /// Removes the creation and modification times, so that writing the same content always
+    /// produces the same bytes. Use this for reproducible builds.
+    #[must_use]
+    pub fn without_timestamps(mut self) -> Self {
+        self.remove(Self::CREATED);
+        self.remove(Self::MODIFIED);
+        self
+    }
+
+    /// Encodes the attributes as varint lengths followed by bytes, prefixed by the total length.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (key, value) in &self.attributes {
+            for bytes in [key.as_bytes(), value.as_slice()] {
+                let (buffer, len) = writer::encode_varint(bytes.len() as u64);
+                body.extend_from_slice(&buffer[..len]);
+                body.extend_from_slice(bytes);
+            }
+        }
+
+        let (buffer, len) = writer::encode_varint(body.len() as u64);
+        [&buffer[..len], &body].concat()
+    }
+
+    /// Decodes attributes encoded by `encode`, without the total length.
+    pub(crate) fn decode(data: &[u8]) -> Result<Self, SnowBinError> {
+        let mut input = SliceInput::new(data);
+        let mut attributes = BTreeMap::new();
+        while !input.is_empty() {
+            let len = reader::read_varint(&mut input)?;
+            let key = core::str::from_utf8(input.take(len)?)
+                .map_err(|_| SnowBinError::MalformedMetadata)?;
+            let len = reader::read_varint(&mut input)?;
+            let value = input.take(len)?;
+
+            match attributes.entry(String::from(key)) {
+                btree_map::Entry::Vacant(entry) => entry.insert(value.to_vec()),
+                btree_map::Entry::Occupied(_) => return Err(SnowBinError::MalformedMetadata),
+            };
+        }
+
+        Ok(Self { attributes })
+    }
+}
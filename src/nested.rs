@@ -0,0 +1,122 @@
+use crate::{
+    error::SnowBinError,
+    format::{self, Encoder, Parent},
+    metadata::SnowBinMetadata,
+    writer::{Output, Patch},
+    SnowBinInfo,
+};
+
+/// Output that streams into the pending entry of a parent encoder.
+pub struct Nested<'a> {
+    parent: &'a mut dyn Parent,
+    base: u64,
+}
+
+impl core::fmt::Debug for Nested<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Nested").field("base", &self.base).finish()
+    }
+}
+
+impl Output for Nested<'_> {
+    fn write(&mut self, data: &[u8]) -> Result<(), SnowBinError> {
+        self.parent.write_raw(data)
+    }
+}
+
+impl Patch for Nested<'_> {
+    fn patch(&mut self, offset: u64, data: &[u8]) -> Result<(), SnowBinError> {
+        self.parent.patch(self.base + offset, data)
+    }
+
+    fn read_back(&mut self, offset: u64, buffer: &mut [u8]) -> Result<(), SnowBinError> {
+        self.parent.read_back(self.base + offset, buffer)
+    }
+
+    fn truncate(&mut self, offset: u64) -> Result<(), SnowBinError> {
+        self.parent.truncate(self.base + offset)
+    }
+}
+
+/// Writes a `SnowBinary` file directly into an entry of another one, without buffering it.
+///
+/// Returned by `write_nested` on `SnowBinWriter`, `SnowBinEncoder`, and `SnowBinNestedWriter`.
+/// The parent cannot be written to until this is closed or dropped.
+#[derive(Debug)]
+pub struct SnowBinNestedWriter<'a> {
+    encoder: Encoder<Nested<'a>>,
+}
+
+impl<'a> SnowBinNestedWriter<'a> {
+    pub(crate) fn new(
+        parent: &'a mut dyn Parent,
+        header: &str,
+        info: SnowBinInfo,
+    ) -> Result<Self, SnowBinError> {
+        let metadata = format::encode_file_metadata(info, &SnowBinMetadata::new())?;
+        parent.begin_entry(header, &SnowBinMetadata::new())?;
+        let base = parent.position();
+
+        let mut encoder = Encoder::unstarted(info, Nested { parent, base });
+        if let Err(e) = encoder.start(&metadata) {
+            let _ = encoder.output_mut().parent.abort_entry();
+            return Err(e);
+        }
+
+        Ok(Self { encoder })
+    }
+
+    /// Writes a header and some data to the nested file.
+    /// # Errors
+    /// Returns `SnowBinError` if the header is too long, the data is too long, the data size is not
+    /// allowed, or the parent could not be written to.
+    pub fn write(&mut self, header: &str, data: &[u8]) -> Result<(), SnowBinError> {
+        self.encoder.write(header, data, &SnowBinMetadata::new())
+    }
+
+    /// Writes a header, some data, and its metadata to the nested file.
+    /// # Errors
+    /// Returns `SnowBinError` if `write` would, or the nested file was not created with
+    /// `SnowBinInfo::with_entry_metadata`.
+    pub fn write_with_metadata(
+        &mut self,
+        header: &str,
+        data: &[u8],
+        metadata: &SnowBinMetadata,
+    ) -> Result<(), SnowBinError> {
+        self.encoder.write(header, data, metadata)
+    }
+
+    /// Starts writing another `SnowBinary` file into an entry of this one.
+    /// # Errors
+    /// Returns `SnowBinError` if the header is too long or the parent could not be written to.
+    pub fn write_nested(
+        &mut self,
+        header: &str,
+        info: SnowBinInfo,
+    ) -> Result<SnowBinNestedWriter<'_>, SnowBinError> {
+        SnowBinNestedWriter::new(&mut self.encoder, header, info)
+    }
+
+    /// Closes the nested file and finishes the entry in the parent.
+    /// Dropping the writer also closes it, but ignores any errors.
+    /// # Errors
+    /// Returns `SnowBinError` if the parent could not be written to, the nested file is too long for
+    /// the parent's data size, or the writer was already closed. A nested file that cannot be
+    /// closed is left out of the parent, which can still be written to.
+    pub fn close(&mut self) -> Result<(), SnowBinError> {
+        if let Err(e) = self.encoder.close() {
+            let _ = self.encoder.output_mut().parent.abort_entry();
+            return Err(e);
+        }
+        self.encoder.output_mut().parent.end_entry()
+    }
+}
+
+impl Drop for SnowBinNestedWriter<'_> {
+    fn drop(&mut self) {
+        if !self.encoder.is_done() {
+            let _ = self.close();
+        }
+    }
+}
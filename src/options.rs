@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+
+#[cfg(any(unix, windows))]
+use crate::shared::SnowBinSharedReader;
+use crate::{
+    error::SnowBinError,
+    file::{SnowBinDropAction, SnowBinReader, SnowBinWriter},
+    limits::SnowBinLimits,
+    map::SnowBinMap,
+    metadata::SnowBinMetadata,
+    SnowBinInfo,
+};
+
+const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// How a reader checks the hash at the end of a file.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum SnowBinVerification {
+    /// Hashes the whole file when it is opened.
+    #[default]
+    Full,
+    /// Does not hash the whole file, so opening a large file is instant. Entries are still checked
+    /// as they are read if the file has outboard hashes, see `SnowBinInfo::with_outboard`.
+    ///
+    /// The outboard hashes are stored in the file itself and are not checked against the hash at
+    /// the end of it, so they only catch accidental corruption. Anyone who can change the data can
+    /// also change its outboard. Use `Full` if the file may have been tampered with.
+    Skip,
+}
+
+/// How headers that are used by more than one entry are handled.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum SnowBinDuplicates {
+    /// Writers accept them and readers return the first entry with the header.
+    #[default]
+    Allow,
+    /// Writers fail with `DuplicateHeader` when a header is written again, and readers fail with
+    /// `DuplicateHeader` when opening a file that has one.
+    Reject,
+}
+
+/// Options for opening `SnowBinReader`s and creating `SnowBinWriter`s.
+///
+/// Default returns `SnowBinOpenOptions` that verify the whole file, allow duplicate headers, have
+/// no limits, buffer 8 KiB, write in place, and do not journal maps.
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+///
+/// use snowbinary::{
+///     SnowBinDuplicates, SnowBinInfo, SnowBinLimits, SnowBinOpenOptions, SnowBinVerification,
+/// };
+///
+/// let options = SnowBinOpenOptions::new()
+///     .duplicates(SnowBinDuplicates::Reject)
+///     .buffer_size(64 * 1024)
+///     .atomic(true);
+/// {
+///     let mut writer = options
+///         .create(SnowBinInfo::default(), PathBuf::from("options.temp"))
+///         .unwrap();
+///     writer.write("Header", b"This is data!").unwrap();
+/// }
+///
+/// let mut reader = options
+///     .verification(SnowBinVerification::Skip)
+///     .limits(SnowBinLimits::new().max_entry_size(1024))
+///     .open(PathBuf::from("options.temp"))
+///     .unwrap();
+/// assert_eq!(reader.read("Header").unwrap(), b"This is data!");
+/// # std::fs::remove_file("options.temp").unwrap();
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SnowBinOpenOptions {
+    pub(crate) verification: SnowBinVerification,
+    pub(crate) duplicates: SnowBinDuplicates,
+    pub(crate) limits: SnowBinLimits,
+    pub(crate) buffer_size: usize,
+    pub(crate) atomic: bool,
+    pub(crate) on_drop: SnowBinDropAction,
+    pub(crate) journal: bool,
+}
+
+impl SnowBinOpenOptions {
+    /// Creates new default `SnowBinOpenOptions`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            verification: SnowBinVerification::Full,
+            duplicates: SnowBinDuplicates::Allow,
+            limits: SnowBinLimits::new(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            atomic: false,
+            on_drop: SnowBinDropAction::Close,
+            journal: false,
+        }
+    }
+
+    /// Sets how readers check the hash of the file.
+    #[must_use]
+    pub const fn verification(mut self, verification: SnowBinVerification) -> Self {
+        self.verification = verification;
+        self
+    }
+
+    /// Sets how headers used by more than one entry are handled.
+    #[must_use]
+    pub const fn duplicates(mut self, duplicates: SnowBinDuplicates) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    /// Sets the limits readers enforce.
+    #[must_use]
+    pub const fn limits(mut self, limits: SnowBinLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets how many bytes readers and writers buffer. 0 turns buffering off.
+    #[must_use]
+    pub const fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Makes writers write to a temporary file next to the path, which is renamed to the path once
+    /// the writer is closed. Readers then never see a partially written file.
+    ///
+    /// The temporary file of `name` is called `.name.<process id>.<writer>.tmp`, so writers never
+    /// share one. If the program crashes before the writer is closed, or it is dropped with
+    /// `SnowBinDropAction::Abort`, the temporary file is left behind and can be deleted.
+    #[must_use]
+    pub const fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Sets what writers do when dropped without being closed. See `SnowBinWriter::on_drop`.
+    #[must_use]
+    pub const fn on_drop(mut self, action: SnowBinDropAction) -> Self {
+        self.on_drop = action;
+        self
+    }
+
+    /// Makes `SnowBinMap`s log every insert and removal to a journal next to the file before making
+    /// it, so changes survive a crash before `SnowBinMap::flush`. Each change is checksummed and
+    /// synced to disk. When a map is opened, the complete changes in its journal are applied to it,
+    /// and a partially written change is discarded. The map is then dirty, and the changes stay in
+    /// the journal until it is flushed. Maps replay a journal they find whether or not this is set.
+    #[must_use]
+    pub const fn journal(mut self, journal: bool) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    /// Creates a `SnowBinWriter` for `path` with these options.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be created or opened, or the file cannot be
+    /// written to.
+    pub fn create(&self, info: SnowBinInfo, path: PathBuf) -> Result<SnowBinWriter, SnowBinError> {
+        self.create_with_metadata(info, path, &SnowBinMetadata::new())
+    }
+
+    /// Creates a `SnowBinWriter` for `path` with these options that stores `metadata` for the
+    /// whole file. See `SnowBinWriter::with_metadata`.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be created or opened, the file cannot be
+    /// written to, or `info` was not created with `SnowBinInfo::with_file_metadata`.
+    pub fn create_with_metadata(
+        &self,
+        info: SnowBinInfo,
+        path: PathBuf,
+        metadata: &SnowBinMetadata,
+    ) -> Result<SnowBinWriter, SnowBinError> {
+        SnowBinWriter::create(info, path, metadata, *self)
+    }
+
+    /// Opens a `SnowBinReader` for `path` with these options.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be opened, the file cannot be read from, the
+    /// hash does not match, or the file exceeds the limits or has duplicate headers.
+    pub fn open(&self, path: PathBuf) -> Result<SnowBinReader, SnowBinError> {
+        SnowBinReader::open_with(path, *self)
+    }
+
+    /// Opens the file at `path` as a `SnowBinMap` with these options. Flushing the map always uses
+    /// atomic writes.
+    /// # Errors
+    /// Returns `SnowBinError` if `open` would.
+    pub fn open_map(&self, path: PathBuf) -> Result<SnowBinMap, SnowBinError> {
+        SnowBinMap::open_with(path, *self)
+    }
+
+    /// Creates an empty file at `path` with `info` and opens it as a `SnowBinMap` with these
+    /// options.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be created, written to, or read back.
+    pub fn create_map(&self, info: SnowBinInfo, path: PathBuf) -> Result<SnowBinMap, SnowBinError> {
+        SnowBinMap::create_with(info, path, *self)
+    }
+
+    /// Opens a `SnowBinSharedReader` for `path` with these options. Reads are not buffered.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be opened, the file cannot be read from, the
+    /// hash does not match, or the file exceeds the limits or has duplicate headers.
+    #[cfg(any(unix, windows))]
+    pub fn open_shared(&self, path: PathBuf) -> Result<SnowBinSharedReader, SnowBinError> {
+        SnowBinSharedReader::open_with(path, *self)
+    }
+}
+
+impl Default for SnowBinOpenOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,247 @@
+//! Bao-style outboard hashes, which let ranges of an entry be verified without hashing the file.
+//!
+//! The data of an entry is split into groups of `GROUP_LEN` bytes. The outboard stores the blake3
+//! chaining value of every group, followed by the blake3 hash of the whole entry. Entries of one
+//! group or less only store the hash.
+//!
+//! The hash of the entry is trusted as stored. It is only covered by the hash at the end of the
+//! file, which is not checked when verification is skipped, so outboards detect corruption but
+//! not deliberate changes.
+
+use alloc::vec::Vec;
+
+use blake3::{
+    hazmat::{self, ChainingValue, HasherExt, Mode},
+    Hasher, CHUNK_LEN,
+};
+
+use crate::HASH_SIZE;
+#[cfg(feature = "std")]
+use crate::{
+    error::SnowBinError,
+    format::Entry,
+    reader::{self, Input},
+};
+
+/// Bytes of data covered by each chaining value.
+pub const GROUP_LEN: u64 = 16 * CHUNK_LEN as u64;
+
+/// Number of chaining values stored for an entry of `size` bytes.
+pub const fn groups(size: u64) -> u64 {
+    if size <= GROUP_LEN {
+        0
+    }
+    else {
+        size.div_ceil(GROUP_LEN)
+    }
+}
+
+/// Length of the outboard of an entry of `size` bytes.
+pub const fn len(size: u64) -> u64 {
+    (groups(size) + 1) * HASH_SIZE as u64
+}
+
+/// Builds the outboard of an entry as its data is written.
+#[derive(Clone, Debug)]
+pub struct OutboardHasher {
+    group: Hasher,
+    group_len: u64,
+    cvs: Vec<ChainingValue>,
+}
+
+impl OutboardHasher {
+    pub fn new() -> Self {
+        Self {
+            group: Hasher::new(),
+            group_len: 0,
+            cvs: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // A full group is only finished once more data arrives, since an entry of exactly one
+            // group is hashed as a root.
+            if self.group_len == GROUP_LEN {
+                self.cvs.push(self.group.finalize_non_root());
+                self.group = Hasher::new();
+                self.group
+                    .set_input_offset(self.cvs.len() as u64 * GROUP_LEN);
+                self.group_len = 0;
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            let take = ((GROUP_LEN - self.group_len) as usize).min(data.len());
+            self.group.update(&data[..take]);
+            self.group_len += take as u64;
+            data = &data[take..];
+        }
+    }
+
+    /// Returns the encoded outboard.
+    pub fn finalize(mut self) -> Vec<u8> {
+        let root = if self.cvs.is_empty() {
+            *self.group.finalize().as_bytes()
+        }
+        else {
+            self.cvs.push(self.group.finalize_non_root());
+            let size = (self.cvs.len() as u64 - 1) * GROUP_LEN + self.group_len;
+            root(&self.cvs, size)
+        };
+
+        let mut outboard = self.cvs.concat();
+        outboard.extend_from_slice(&root);
+        outboard
+    }
+}
+
+/// The outboard of an entry, checked against the entry hash.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct Outboard {
+    size: u64,
+    root: [u8; HASH_SIZE as usize],
+    cvs: Vec<ChainingValue>,
+}
+
+#[cfg(feature = "std")]
+impl Outboard {
+    /// Reads the outboard stored after the data of `entry`.
+    pub fn read<I: Input>(input: &mut I, entry: &Entry) -> Result<Self, SnowBinError> {
+        input.seek(entry.offset + entry.size)?;
+
+        // The walker checked that the outboard fits in the input.
+        #[allow(clippy::cast_possible_truncation)]
+        let mut cvs = Vec::with_capacity(groups(entry.size) as usize);
+        for _ in 0..groups(entry.size) {
+            let mut cv = [0_u8; HASH_SIZE as usize];
+            input.read(&mut cv)?;
+            cvs.push(cv);
+        }
+        let mut root = [0_u8; HASH_SIZE as usize];
+        input.read(&mut root)?;
+
+        if !cvs.is_empty() && self::root(&cvs, entry.size) != root {
+            return Err(SnowBinError::HashDoesNotMatch);
+        }
+
+        Ok(Self {
+            size: entry.size,
+            root,
+            cvs,
+        })
+    }
+
+    /// Byte range of group `index` within the entry.
+    pub fn group_range(&self, index: u64) -> (u64, u64) {
+        let start = index * GROUP_LEN;
+        (start, (start + GROUP_LEN).min(self.size))
+    }
+
+    /// Checks the data of group `index` against the outboard.
+    pub fn verify(&self, index: u64, data: &[u8]) -> Result<(), SnowBinError> {
+        let matches = if self.cvs.is_empty() {
+            blake3::hash(data) == self.root
+        }
+        else {
+            let mut hasher = Hasher::new();
+            hasher.set_input_offset(index * GROUP_LEN);
+            hasher.update(data);
+            #[allow(clippy::cast_possible_truncation)]
+            self.cvs
+                .get(index as usize)
+                .is_some_and(|cv| hasher.finalize_non_root() == *cv)
+        };
+
+        if matches {
+            Ok(())
+        }
+        else {
+            Err(SnowBinError::HashDoesNotMatch)
+        }
+    }
+}
+
+/// Reads group `index` of `entry` and checks it against `outboard`.
+#[cfg(feature = "std")]
+pub fn read_group<I: Input>(
+    input: &mut I,
+    entry: &Entry,
+    outboard: &Outboard,
+    index: u64,
+) -> Result<Vec<u8>, SnowBinError> {
+    let (start, end) = outboard.group_range(index);
+    input.seek(entry.offset + start)?;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let mut buffer = alloc::vec![0_u8; (end - start) as usize];
+    input.read(&mut buffer)?;
+    outboard.verify(index, &buffer)?;
+
+    Ok(buffer)
+}
+
+/// Reads `start..end` of `entry`, checking every group it touches if there is an outboard.
+#[cfg(feature = "std")]
+pub fn read_range<I: Input>(
+    input: &mut I,
+    entry: &Entry,
+    outboard: Option<&Outboard>,
+    start: u64,
+    end: u64,
+) -> Result<Vec<u8>, SnowBinError> {
+    if start > end || end > entry.size {
+        return Err(SnowBinError::RangeOutOfBounds { size: entry.size });
+    }
+
+    let Some(outboard) = outboard
+    else {
+        input.seek(entry.offset + start)?;
+        return reader::read_bytes(input, end - start);
+    };
+
+    let mut data = Vec::new();
+    let mut index = start / GROUP_LEN;
+    while index * GROUP_LEN < end {
+        let group = read_group(input, entry, outboard, index)?;
+        let group_start = index * GROUP_LEN;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let from = (start.max(group_start) - group_start) as usize;
+        #[allow(clippy::cast_possible_truncation)]
+        let to = (end - group_start).min(group.len() as u64) as usize;
+        data.extend_from_slice(&group[from..to]);
+        index += 1;
+    }
+
+    Ok(data)
+}
+
+/// Hash of an entry of `size` bytes, from the chaining values of its groups.
+fn root(cvs: &[ChainingValue], size: u64) -> [u8; HASH_SIZE as usize] {
+    let left = hazmat::left_subtree_len(size);
+    #[allow(clippy::cast_possible_truncation)]
+    let split = (left / GROUP_LEN) as usize;
+    let hash = hazmat::merge_subtrees_root(
+        &subtree(&cvs[..split], left),
+        &subtree(&cvs[split..], size - left),
+        Mode::Hash,
+    );
+    *hash.as_bytes()
+}
+
+/// Chaining value of a subtree of `size` bytes, from the chaining values of its groups.
+fn subtree(cvs: &[ChainingValue], size: u64) -> ChainingValue {
+    if cvs.len() == 1 {
+        return cvs[0];
+    }
+
+    let left = hazmat::left_subtree_len(size);
+    #[allow(clippy::cast_possible_truncation)]
+    let split = (left / GROUP_LEN) as usize;
+    hazmat::merge_subtrees_non_root(
+        &subtree(&cvs[..split], left),
+        &subtree(&cvs[split..], size - left),
+        Mode::Hash,
+    )
+}
@@ -0,0 +1,44 @@
+//! Helpers for path style headers such as `textures/ui/button`.
+
+/// Removes any trailing separators from `prefix`.
+fn trim(prefix: &str, separator: char) -> &str {
+    prefix.trim_end_matches(separator)
+}
+
+/// Returns true if `header` has no empty components.
+pub fn is_valid(header: &str, separator: char) -> bool {
+    header
+        .split(separator)
+        .all(|component| !component.is_empty())
+}
+
+/// Returns true if `name` is `prefix` or is nested under it. An empty prefix matches everything.
+pub fn in_subtree(name: &str, prefix: &str, separator: char) -> bool {
+    let prefix = trim(prefix, separator);
+    if prefix.is_empty() {
+        return true;
+    }
+
+    name.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(separator))
+}
+
+/// Returns the path of the direct child of `prefix` that `name` is in, if any.
+pub fn child<'a>(name: &'a str, prefix: &str, separator: char) -> Option<&'a str> {
+    let prefix = trim(prefix, separator);
+    let start = if prefix.is_empty() {
+        0
+    }
+    else {
+        let rest = name.strip_prefix(prefix)?.strip_prefix(separator)?;
+        name.len() - rest.len()
+    };
+
+    let rest = &name[start..];
+    if rest.is_empty() {
+        return None;
+    }
+
+    let end = rest.find(separator).map_or(name.len(), |i| start + i);
+    Some(&name[..end])
+}
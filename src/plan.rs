@@ -0,0 +1,193 @@
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+use crate::{
+    error::SnowBinError,
+    memory::SnowBinEncoder,
+    metadata::SnowBinMetadata,
+    schema::{SnowBinSchema, SnowBinViolation},
+    SnowBinInfo, DATA_SIZES, DEFAULT_HEADER_SIZE,
+};
+#[cfg(feature = "std")]
+use crate::{file::SnowBinWriter, summary::SnowBinWriteSummary};
+
+/// Works out the smallest `SnowBinInfo` that can hold a set of entries, from their headers and
+/// data lengths.
+///
+/// The header size is set to the longest header and the data size to the narrowest that fits the
+/// longest data, so writing never fails with `HeaderTooLong` or `DataTooLong` and no space is
+/// wasted. Spec 3 options are taken from the `SnowBinInfo` given to `with_options`.
+/// # Example
+/// ```
+/// use snowbinary::SnowBinPlan;
+///
+/// let mut plan = SnowBinPlan::new();
+/// plan.add("textures/ui/button", 300);
+/// plan.add("readme", 20);
+/// let info = plan.info().unwrap();
+///
+/// assert_eq!(info.header_size(), 18);
+/// assert_eq!(info.data_size(), 16);
+/// ```
+#[allow(clippy::module_name_repetitions)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SnowBinPlan {
+    options: SnowBinInfo,
+    header_len: usize,
+    max_size: u64,
+}
+
+impl SnowBinPlan {
+    /// Creates a new `SnowBinPlan` without any Spec 3 options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_options(SnowBinInfo::default())
+    }
+
+    /// Creates a new `SnowBinPlan` that keeps the Spec 3 options of `options`, such as
+    /// `SnowBinInfo::with_outboard`. Its header size and data size are ignored.
+    #[must_use]
+    pub const fn with_options(options: SnowBinInfo) -> Self {
+        Self {
+            options,
+            header_len: 0,
+            max_size: 0,
+        }
+    }
+
+    /// Adds an entry with `header` and `size` bytes of data.
+    pub fn add(&mut self, header: &str, size: u64) {
+        self.header_len = self.header_len.max(header.len());
+        self.max_size = self.max_size.max(size);
+    }
+
+    /// Returns the smallest `SnowBinInfo` that can hold every entry added so far.
+    /// # Errors
+    /// Returns `SnowBinError` if a header is longer than any header size allows.
+    pub fn info(&self) -> Result<SnowBinInfo, SnowBinError> {
+        let header_size = match u32::try_from(self.header_len) {
+            // u32::MAX marks the end of the entries of length prefixed headers.
+            Ok(u32::MAX) if self.options.variable_headers() => {
+                return Err(SnowBinError::HeaderTooLong)
+            }
+            Ok(len) => len.max(DEFAULT_HEADER_SIZE),
+            Err(_) => return Err(SnowBinError::HeaderTooLong),
+        };
+
+        let data_size = if self.options.varint_sizes() {
+            DATA_SIZES[0]
+        }
+        else {
+            DATA_SIZES
+                .into_iter()
+                .find(|&bits| bits == 64 || self.max_size >> bits == 0)
+                .unwrap_or(64)
+        };
+
+        let mut info = self.options;
+        info.header_size = header_size;
+        info.data_size = data_size;
+        Ok(info)
+    }
+}
+
+impl Default for SnowBinPlan {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Holds entries in memory until they are all known, then writes them with the smallest
+/// `SnowBinInfo` that fits, see `SnowBinPlan`.
+/// # Example
+/// ```
+/// use snowbinary::{SnowBinDecoder, SnowBinStagedWriter};
+///
+/// let mut staged = SnowBinStagedWriter::new();
+/// staged.write("A much longer header than 8 bytes", b"This is data!");
+/// staged.write("Header", &[0_u8; 300]);
+/// let bytes = staged.finish().unwrap();
+///
+/// let decoder = SnowBinDecoder::new(&bytes).unwrap();
+/// assert_eq!(decoder.read("Header").unwrap(), [0_u8; 300]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SnowBinStagedWriter {
+    plan: SnowBinPlan,
+    entries: Vec<(String, Vec<u8>, SnowBinMetadata)>,
+}
+
+impl SnowBinStagedWriter {
+    /// Creates a new empty `SnowBinStagedWriter` without any Spec 3 options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_options(SnowBinInfo::default())
+    }
+
+    /// Creates a new empty `SnowBinStagedWriter` that keeps the Spec 3 options of `options`.
+    /// See `SnowBinPlan::with_options`.
+    #[must_use]
+    pub const fn with_options(options: SnowBinInfo) -> Self {
+        Self {
+            plan: SnowBinPlan::with_options(options),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Stages a header and some data.
+    pub fn write(&mut self, header: &str, data: &[u8]) {
+        self.write_with_metadata(header, data, &SnowBinMetadata::new());
+    }
+
+    /// Stages a header, some data, and its metadata, which needs
+    /// `SnowBinInfo::with_entry_metadata` in the options.
+    pub fn write_with_metadata(&mut self, header: &str, data: &[u8], metadata: &SnowBinMetadata) {
+        self.plan.add(header, data.len() as u64);
+        self.entries
+            .push((String::from(header), data.to_vec(), metadata.clone()));
+    }
+
+    /// Returns the `SnowBinInfo` the staged entries will be written with.
+    /// # Errors
+    /// Returns `SnowBinError` if a header is longer than any header size allows.
+    pub fn info(&self) -> Result<SnowBinInfo, SnowBinError> {
+        self.plan.info()
+    }
+
+    /// Checks the staged entries against `schema` before they are written, returning every
+    /// violation. See `SnowBinSchema`.
+    #[must_use]
+    pub fn validate(&self, schema: &SnowBinSchema) -> Vec<SnowBinViolation> {
+        schema.validate_all(
+            self.entries
+                .iter()
+                .map(|(header, data, _)| (header.as_str(), data.as_slice())),
+        )
+    }
+
+    /// Encodes the staged entries into memory. Works without `std`.
+    /// # Errors
+    /// Returns `SnowBinError` if a header is longer than any header size allows, or an entry could
+    /// not be encoded.
+    pub fn finish(self) -> Result<Vec<u8>, SnowBinError> {
+        let mut encoder = SnowBinEncoder::new(self.info()?)?;
+        for (header, data, metadata) in &self.entries {
+            encoder.write_with_metadata(header, data, metadata)?;
+        }
+        encoder.finish()
+    }
+
+    /// Writes the staged entries to a file at `path`.
+    /// # Errors
+    /// Returns `SnowBinError` if a header is longer than any header size allows, the file could not
+    /// be created or opened, or the file cannot be written to.
+    #[cfg(feature = "std")]
+    pub fn write_file(self, path: PathBuf) -> Result<SnowBinWriteSummary, SnowBinError> {
+        let mut writer = SnowBinWriter::new(self.info()?, path)?;
+        for (header, data, metadata) in &self.entries {
+            writer.write_with_metadata(header, data, metadata)?;
+        }
+        writer.finish()
+    }
+}
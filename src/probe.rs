@@ -0,0 +1,158 @@
+#[cfg(feature = "std")]
+use std::{fs::File, io::Read, path::PathBuf};
+
+#[cfg(feature = "std")]
+use crate::DATA_START_V3;
+use crate::{error::SnowBinError, format, SnowBinInfo, DATA_SIZES, MIN_VERSION_SPEC, VERSION_SPEC};
+
+/// What `probe` found at the start of some bytes.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum SnowBinProbe {
+    /// The bytes do not start with the `SNOW_BIN` magic.
+    NotSnowBinary,
+    /// The bytes start with the `SNOW_BIN` magic, but end before the rest of the preamble.
+    Truncated,
+    /// A `SnowBinary` file with a Spec version this library can read.
+    Supported {
+        /// Spec version of the file.
+        version: u64,
+        /// Max length of a header in bytes.
+        header_size: u32,
+        /// Width of each entry's data length in bits.
+        data_size: u8,
+    },
+    /// A `SnowBinary` file with a Spec version this library cannot read.
+    UnsupportedVersion {
+        /// Spec version of the file.
+        version: u64,
+    },
+    /// A `SnowBinary` file whose data size is not 8, 16, 32, or 64 bits.
+    DataSizeNotAllowed {
+        /// Width of each entry's data length in bits.
+        data_size: u8,
+    },
+    /// A Spec 3 `SnowBinary` file with flags this library cannot read.
+    UnsupportedFlags {
+        /// Flags of the file.
+        flags: u32,
+    },
+    /// A Spec 3 `SnowBinary` file whose flags are known but not valid together, such as an
+    /// alignment above 16, a separator that is not ASCII punctuation, or no flags at all.
+    MalformedFlags {
+        /// Flags of the file.
+        flags: u32,
+    },
+}
+
+impl SnowBinProbe {
+    /// Returns true if the bytes start with the `SNOW_BIN` magic, whatever their version.
+    #[must_use]
+    pub const fn is_snowbinary(&self) -> bool {
+        !matches!(self, Self::NotSnowBinary)
+    }
+
+    /// Returns true if the file has a Spec version this library can read.
+    #[must_use]
+    pub const fn is_supported(&self) -> bool {
+        matches!(self, Self::Supported { .. })
+    }
+}
+
+/// Checks whether `data` starts like a `SnowBinary` file this library can read.
+///
+/// Only the 21 byte preamble is looked at, or 25 bytes with the flags of Spec 3 files. Nothing is
+/// hashed, so a `Supported` result does not mean the rest of the file is valid.
+/// # Example
+/// ```
+/// use snowbinary::{SnowBinEncoder, SnowBinInfo, SnowBinProbe};
+///
+/// let bytes = SnowBinEncoder::new(SnowBinInfo::new(16, 32).unwrap())
+///     .unwrap()
+///     .finish()
+///     .unwrap();
+///
+/// assert_eq!(
+///     snowbinary::probe(&bytes),
+///     SnowBinProbe::Supported {
+///         version: 2,
+///         header_size: 16,
+///         data_size: 32
+///     }
+/// );
+/// assert_eq!(snowbinary::probe(b"PK\x03\x04"), SnowBinProbe::NotSnowBinary);
+/// ```
+#[must_use]
+pub fn probe(data: &[u8]) -> SnowBinProbe {
+    if !data.starts_with(b"SNOW_BIN") {
+        return SnowBinProbe::NotSnowBinary;
+    }
+
+    let Some(version) = data.get(8..16)
+    else {
+        return SnowBinProbe::Truncated;
+    };
+    let version = u64::from_le_bytes(version.try_into().unwrap_or_default());
+    if !(MIN_VERSION_SPEC..=VERSION_SPEC).contains(&version) {
+        return SnowBinProbe::UnsupportedVersion { version };
+    }
+
+    let (Some(header_size), Some(&data_size)) = (data.get(16..20), data.get(20))
+    else {
+        return SnowBinProbe::Truncated;
+    };
+    if !DATA_SIZES.contains(&data_size) {
+        return SnowBinProbe::DataSizeNotAllowed { data_size };
+    }
+
+    let flags = if version >= 3 {
+        let Some(flags) = data.get(21..25)
+        else {
+            return SnowBinProbe::Truncated;
+        };
+        u32::from_le_bytes(flags.try_into().unwrap_or_default())
+    }
+    else {
+        0
+    };
+    let header_size = u32::from_le_bytes(header_size.try_into().unwrap_or_default());
+    let info = SnowBinInfo {
+        header_size,
+        data_size,
+        flags,
+    };
+    match format::check_flags(info, version) {
+        Ok(()) => (),
+        Err(SnowBinError::UnsupportedFlags { .. }) => {
+            return SnowBinProbe::UnsupportedFlags { flags };
+        }
+        Err(_) => return SnowBinProbe::MalformedFlags { flags },
+    }
+
+    SnowBinProbe::Supported {
+        version,
+        header_size,
+        data_size,
+    }
+}
+
+/// Probes the file at `path`, reading only its preamble. See `probe`.
+/// # Errors
+/// Returns `SnowBinError` if the file could not be opened or cannot be read from.
+#[cfg(feature = "std")]
+pub fn probe_path(path: PathBuf) -> Result<SnowBinProbe, SnowBinError> {
+    let file = File::open(&path)
+        .map_err(|source| SnowBinError::CouldNotCreateOrOpenFile { path, source })?;
+
+    let mut preamble = Vec::new();
+    file.take(DATA_START_V3)
+        .read_to_end(&mut preamble)
+        .map_err(|source| SnowBinError::IOReadError {
+            offset: 0,
+            header: None,
+            source,
+        })?;
+
+    Ok(probe(&preamble))
+}
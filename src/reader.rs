@@ -1,79 +1,378 @@
-use std::{convert::TryInto, fs::File, io::Read, mem};
+use alloc::{string::String, vec, vec::Vec};
+#[cfg(all(feature = "std", any(unix, windows)))]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
 
 use crate::error::SnowBinError;
 
-#[inline]
-pub fn error(result: std::io::Result<usize>) -> Result<usize, SnowBinError> {
-    result.map_or(Err(SnowBinError::IOWriteError), Ok)
+/// A seekable source of bytes that the format code reads from.
+pub trait Input {
+    /// Current byte offset from the start of the input.
+    fn position(&self) -> u64;
+
+    /// Total length of the input in bytes.
+    fn len(&mut self) -> Result<u64, SnowBinError>;
+
+    /// Moves to `offset` bytes from the start of the input.
+    fn seek(&mut self, offset: u64) -> Result<(), SnowBinError>;
+
+    /// Fills `buffer` from the input, failing with `UnexpectedEof` if the input ends first.
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), SnowBinError>;
+
+    /// Skips over `length` bytes.
+    fn skip(&mut self, length: u64) -> Result<(), SnowBinError> {
+        let offset = self
+            .position()
+            .checked_add(length)
+            .ok_or(SnowBinError::ReachedEOF)?;
+        self.seek(offset)
+    }
+}
+
+/// An `Input` over a borrowed byte slice.
+#[derive(Copy, Clone, Debug)]
+pub struct SliceInput<'a> {
+    data: &'a [u8],
+    offset: u64,
+}
+
+impl<'a> SliceInput<'a> {
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    /// Borrows the next `length` bytes without copying them.
+    pub fn take(&mut self, length: u64) -> Result<&'a [u8], SnowBinError> {
+        let start = self.remaining_start();
+        let available = (self.data.len() - start) as u64;
+        if length > available {
+            return Err(SnowBinError::UnexpectedEof {
+                offset: self.offset,
+                expected: length,
+                actual: available,
+            });
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let end = start + length as usize;
+        self.offset += length;
+        Ok(&self.data[start..end])
+    }
+
+    /// Returns true if every byte has been read.
+    pub fn is_empty(&self) -> bool {
+        self.remaining_start() == self.data.len()
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn remaining_start(&self) -> usize {
+        (self.offset as usize).min(self.data.len())
+    }
+}
+
+impl Input for SliceInput<'_> {
+    fn position(&self) -> u64 {
+        self.offset
+    }
+
+    fn len(&mut self) -> Result<u64, SnowBinError> {
+        Ok(self.data.len() as u64)
+    }
+
+    fn seek(&mut self, offset: u64) -> Result<(), SnowBinError> {
+        self.offset = offset;
+        Ok(())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), SnowBinError> {
+        let bytes = self.take(buffer.len() as u64)?;
+        buffer.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// An `Input` over anything that implements `Read` and `Seek`, tracking the current offset.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct IoInput<R> {
+    inner: R,
+    offset: u64,
+    base: u64,
+    len: Option<u64>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> IoInput<R> {
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            base: 0,
+            len: None,
+        }
+    }
+
+    /// Creates an `IoInput` over `len` bytes of `inner` starting at `base`.
+    /// Offsets are relative to `base`.
+    pub fn window(mut inner: R, base: u64, len: u64) -> Result<Self, SnowBinError> {
+        inner
+            .seek(SeekFrom::Start(base))
+            .map_err(|source| SnowBinError::IOReadError {
+                offset: base,
+                header: None,
+                source,
+            })?;
+
+        Ok(Self {
+            inner,
+            offset: 0,
+            base,
+            len: Some(len),
+        })
+    }
+
+    #[cfg(feature = "parallel")]
+    pub const fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Offset in the underlying reader of `offset` in this input.
+    pub const fn absolute(&self, offset: u64) -> u64 {
+        self.base + offset
+    }
+
+    pub fn error<T>(
+        &mut self,
+        op: impl FnOnce(&mut R) -> std::io::Result<T>,
+    ) -> Result<T, SnowBinError> {
+        let offset = self.offset;
+        op(&mut self.inner).map_err(|source| SnowBinError::IOReadError {
+            offset,
+            header: None,
+            source,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> Input for IoInput<R> {
+    fn position(&self) -> u64 {
+        self.offset
+    }
+
+    fn len(&mut self) -> Result<u64, SnowBinError> {
+        if let Some(len) = self.len {
+            return Ok(len);
+        }
+
+        let offset = self.offset;
+        self.error(|r| {
+            let len = r.seek(SeekFrom::End(0))?;
+            r.seek(SeekFrom::Start(offset))?;
+            Ok(len)
+        })
+    }
+
+    fn seek(&mut self, offset: u64) -> Result<(), SnowBinError> {
+        if offset != self.offset {
+            let base = self.base;
+            self.offset = self.error(|r| r.seek(SeekFrom::Start(base + offset)))? - base;
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), SnowBinError> {
+        let offset = self.offset;
+        #[allow(clippy::cast_possible_truncation)]
+        let wanted = self.len.map_or(buffer.len(), |len| {
+            (len.saturating_sub(offset).min(buffer.len() as u64)) as usize
+        });
+
+        let mut filled = 0;
+        while filled < wanted {
+            match self.inner.read(&mut buffer[filled..wanted]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(source) => {
+                    self.offset += filled as u64;
+                    return Err(SnowBinError::IOReadError {
+                        offset: offset + filled as u64,
+                        header: None,
+                        source,
+                    });
+                }
+            }
+        }
+        self.offset += filled as u64;
+
+        if filled < buffer.len() {
+            return Err(SnowBinError::UnexpectedEof {
+                offset,
+                expected: buffer.len() as u64,
+                actual: filled as u64,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// An `Input` over `len` bytes of a shared `File` starting at `base`, using positional reads that
+/// do not move the file cursor. Any number of them can read the same file at once.
+#[cfg(all(feature = "std", any(unix, windows)))]
+#[derive(Copy, Clone, Debug)]
+pub struct AtInput<'a> {
+    file: &'a File,
+    offset: u64,
+    base: u64,
+    len: u64,
+}
+
+#[cfg(all(feature = "std", any(unix, windows)))]
+impl<'a> AtInput<'a> {
+    pub const fn new(file: &'a File, base: u64, len: u64) -> Self {
+        Self {
+            file,
+            offset: 0,
+            base,
+            len,
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    pub const fn file(&self) -> &'a File {
+        self.file
+    }
+
+    /// Offset in the file of `offset` in this input.
+    #[cfg(feature = "parallel")]
+    pub const fn absolute(&self, offset: u64) -> u64 {
+        self.base + offset
+    }
+
+    #[cfg(unix)]
+    fn read_at(&self, buffer: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        std::os::unix::fs::FileExt::read_at(self.file, buffer, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_at(&self, buffer: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        std::os::windows::fs::FileExt::seek_read(self.file, buffer, offset)
+    }
 }
 
-pub fn read_header(file: &mut File, header_len: u32) -> Result<String, SnowBinError> {
+#[cfg(all(feature = "std", any(unix, windows)))]
+impl Input for AtInput<'_> {
+    fn position(&self) -> u64 {
+        self.offset
+    }
+
+    fn len(&mut self) -> Result<u64, SnowBinError> {
+        Ok(self.len)
+    }
+
+    fn seek(&mut self, offset: u64) -> Result<(), SnowBinError> {
+        self.offset = offset;
+        Ok(())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<(), SnowBinError> {
+        let offset = self.offset;
+        #[allow(clippy::cast_possible_truncation)]
+        let wanted = (self.len.saturating_sub(offset).min(buffer.len() as u64)) as usize;
+
+        let mut filled = 0;
+        while filled < wanted {
+            match self.read_at(
+                &mut buffer[filled..wanted],
+                self.base + offset + filled as u64,
+            ) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(source) => {
+                    self.offset += filled as u64;
+                    return Err(SnowBinError::IOReadError {
+                        offset: offset + filled as u64,
+                        header: None,
+                        source,
+                    });
+                }
+            }
+        }
+        self.offset += filled as u64;
+
+        if filled < buffer.len() {
+            return Err(SnowBinError::UnexpectedEof {
+                offset,
+                expected: buffer.len() as u64,
+                actual: filled as u64,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+pub fn read_header<I: Input>(input: &mut I, header_len: u32) -> Result<String, SnowBinError> {
     let mut buffer = vec![32_u8; header_len as usize];
-    error(file.by_ref().take(u64::from(header_len)).read(&mut buffer))?;
+    input.read(&mut buffer)?;
 
     String::from_utf8(buffer).map_err(|_| SnowBinError::MalformedHeader)
 }
 
-pub fn read_bytes(file: &mut File, length: u64) -> Result<Vec<u8>, SnowBinError> {
+pub fn read_bytes<I: Input>(input: &mut I, length: u64) -> Result<Vec<u8>, SnowBinError> {
     #[allow(clippy::cast_possible_truncation)]
     let mut buffer = vec![0_u8; length as usize];
-    error(file.by_ref().take(length).read(&mut buffer))?;
+    input.read(&mut buffer)?;
 
     Ok(buffer)
 }
 
-pub fn read_u8(file: &mut File) -> Result<u8, SnowBinError> {
+pub fn read_u8<I: Input>(input: &mut I) -> Result<u8, SnowBinError> {
     let mut buffer = [0_u8; 1];
-    error(file.by_ref().take(1).read(&mut buffer))?;
-
-    let (uint_bytes, _) = buffer.split_at(mem::size_of::<u8>());
-    let uint = u8::from_le_bytes(
-        uint_bytes
-            .try_into()
-            .map_err(|_| SnowBinError::MalformedUInt)?,
-    );
+    input.read(&mut buffer)?;
 
-    Ok(uint)
+    Ok(u8::from_le_bytes(buffer))
 }
 
-pub fn read_u16(file: &mut File) -> Result<u16, SnowBinError> {
+pub fn read_u16<I: Input>(input: &mut I) -> Result<u16, SnowBinError> {
     let mut buffer = [0_u8; 2];
-    error(file.by_ref().take(2).read(&mut buffer))?;
+    input.read(&mut buffer)?;
 
-    let (uint_bytes, _) = buffer.split_at(mem::size_of::<u16>());
-    let uint = u16::from_le_bytes(
-        uint_bytes
-            .try_into()
-            .map_err(|_| SnowBinError::MalformedUInt)?,
-    );
-
-    Ok(uint)
+    Ok(u16::from_le_bytes(buffer))
 }
 
-pub fn read_u32(file: &mut File) -> Result<u32, SnowBinError> {
+pub fn read_u32<I: Input>(input: &mut I) -> Result<u32, SnowBinError> {
     let mut buffer = [0_u8; 4];
-    error(file.by_ref().take(4).read(&mut buffer))?;
-
-    let (uint_bytes, _) = buffer.split_at(mem::size_of::<u32>());
-    let uint = u32::from_le_bytes(
-        uint_bytes
-            .try_into()
-            .map_err(|_| SnowBinError::MalformedUInt)?,
-    );
+    input.read(&mut buffer)?;
 
-    Ok(uint)
+    Ok(u32::from_le_bytes(buffer))
 }
 
-pub fn read_u64(file: &mut File) -> Result<u64, SnowBinError> {
+pub fn read_u64<I: Input>(input: &mut I) -> Result<u64, SnowBinError> {
     let mut buffer = [0_u8; 8];
-    error(file.by_ref().take(8).read(&mut buffer))?;
+    input.read(&mut buffer)?;
+
+    Ok(u64::from_le_bytes(buffer))
+}
+
+pub fn read_varint<I: Input>(input: &mut I) -> Result<u64, SnowBinError> {
+    let mut uint = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let byte = read_u8(input)?;
+        let bits = u64::from(byte & 0x7f);
+        if shift == 63 && bits > 1 {
+            return Err(SnowBinError::MalformedUInt);
+        }
 
-    let (uint_bytes, _) = buffer.split_at(mem::size_of::<u64>());
-    let uint = u64::from_le_bytes(
-        uint_bytes
-            .try_into()
-            .map_err(|_| SnowBinError::MalformedUInt)?,
-    );
+        uint |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(uint);
+        }
+    }
 
-    Ok(uint)
+    Err(SnowBinError::MalformedUInt)
 }
@@ -0,0 +1,247 @@
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use crate::{
+    error::SnowBinError,
+    format::{self, Entry, Walker},
+    limits::SnowBinLimits,
+    memory::SnowBinEncoder,
+    metadata::SnowBinMetadata,
+    outboard::{self, OutboardHasher},
+    reader::{self, Input, SliceInput},
+    SnowBinInfo, HASH_SIZE,
+};
+#[cfg(feature = "std")]
+use crate::{file::SnowBinWriter, reader::IoInput, summary::SnowBinWriteSummary};
+
+/// An entry recovered by `SnowBinSalvage`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SnowBinSalvagedEntry {
+    header: String,
+    data: Vec<u8>,
+    metadata: SnowBinMetadata,
+}
+
+impl SnowBinSalvagedEntry {
+    /// Returns the header, without padding.
+    #[must_use]
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    /// Returns the data.
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the metadata, which is empty for files without entry metadata.
+    #[must_use]
+    pub const fn metadata(&self) -> &SnowBinMetadata {
+        &self.metadata
+    }
+}
+
+/// Recovers what it can from a `SnowBinary` file that cannot be read normally, such as one whose
+/// writer crashed before it was closed, or whose hash no longer matches.
+///
+/// The hash is not needed. Entries are walked from the start of the file for as long as their
+/// framing stays consistent, and every entry read in full is kept. If the file has outboard hashes,
+/// entries whose data does not match them are left out and listed in `damaged`.
+/// # Example
+/// ```
+/// use snowbinary::{SnowBinDecoder, SnowBinEncoder, SnowBinInfo, SnowBinSalvage};
+///
+/// let mut encoder = SnowBinEncoder::new(SnowBinInfo::default()).unwrap();
+/// encoder.write("Header", b"This is data!").unwrap();
+/// let bytes = encoder.finish().unwrap();
+/// // Cut off the end header and hash, as if the writer crashed.
+/// let crashed = &bytes[..bytes.len() - 40];
+/// assert!(SnowBinDecoder::new(crashed).is_err());
+///
+/// let salvage = SnowBinSalvage::new(crashed).unwrap();
+/// assert_eq!(salvage.entries()[0].data(), b"This is data!");
+/// assert!(!salvage.is_complete());
+///
+/// let recovered = salvage.encode().unwrap();
+/// let decoder = SnowBinDecoder::new(&recovered).unwrap();
+/// assert_eq!(decoder.read("Header").unwrap(), b"This is data!");
+/// ```
+#[derive(Debug)]
+pub struct SnowBinSalvage {
+    info: SnowBinInfo,
+    metadata: SnowBinMetadata,
+    entries: Vec<SnowBinSalvagedEntry>,
+    damaged: Vec<String>,
+    stopped_at: u64,
+    error: Option<SnowBinError>,
+    hash_matches: bool,
+}
+
+impl SnowBinSalvage {
+    /// Salvages the entries of the `SnowBinary` file in `data`. Works without `std`.
+    /// # Errors
+    /// Returns `SnowBinError` if the file info at the start of `data` cannot be read, in which
+    /// case nothing can be salvaged.
+    pub fn new(data: &[u8]) -> Result<Self, SnowBinError> {
+        Self::salvage(&mut SliceInput::new(data))
+    }
+
+    /// Reads and salvages the `SnowBinary` file at `path`, without reading all of it into memory
+    /// first.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be opened, or its file info cannot be read.
+    #[cfg(feature = "std")]
+    pub fn from_path(path: PathBuf) -> Result<Self, SnowBinError> {
+        let file = File::open(&path)
+            .map_err(|source| SnowBinError::CouldNotCreateOrOpenFile { path, source })?;
+        Self::salvage(&mut IoInput::new(BufReader::new(file)))
+    }
+
+    fn salvage<I: Input>(input: &mut I) -> Result<Self, SnowBinError> {
+        let limits = SnowBinLimits::new();
+        let info = format::read_info(input, limits)?;
+        // A damaged file metadata block is dropped, the walker stops if it cannot be skipped.
+        let metadata = format::read_file_metadata(input, info, limits).unwrap_or_default();
+
+        let mut salvage = Self {
+            info,
+            metadata,
+            entries: Vec::new(),
+            damaged: Vec::new(),
+            stopped_at: info.data_start(),
+            error: None,
+            hash_matches: false,
+        };
+
+        let mut walker = match Walker::new(input, info, limits) {
+            Ok(walker) => walker,
+            Err(e) => {
+                salvage.error = Some(e);
+                return Ok(salvage);
+            }
+        };
+
+        loop {
+            salvage.stopped_at = walker.position();
+            let added = match walker.next(input) {
+                Ok(Some(entry)) => salvage.add(input, &entry),
+                Ok(None) => break,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = added {
+                salvage.error = Some(e);
+                return Ok(salvage);
+            }
+        }
+
+        // The end header was found, so the hash should follow it.
+        let end = input.position();
+        salvage.hash_matches = input
+            .len()
+            .is_ok_and(|len| len == end + u64::from(HASH_SIZE))
+            && format::verify(input, limits).is_ok();
+
+        Ok(salvage)
+    }
+
+    /// Keeps `entry` if its data and metadata are intact.
+    fn add<I: Input>(&mut self, input: &mut I, entry: &Entry) -> Result<(), SnowBinError> {
+        // The walker checked that the outboard and metadata fit in the input.
+        input.seek(entry.offset)?;
+        let payload = reader::read_bytes(input, entry.size)?;
+        let intact = !self.info.outboard() || {
+            let mut hasher = OutboardHasher::new();
+            hasher.update(&payload);
+            hasher.finalize() == reader::read_bytes(input, outboard::len(entry.size))?
+        };
+        input.seek(entry.metadata_offset)?;
+        let metadata = SnowBinMetadata::decode(&reader::read_bytes(input, entry.metadata_size)?);
+
+        match metadata {
+            Ok(metadata) if intact => self.entries.push(SnowBinSalvagedEntry {
+                header: String::from(entry.name(self.info)),
+                data: payload,
+                metadata,
+            }),
+            _ => self.damaged.push(String::from(entry.name(self.info))),
+        }
+        Ok(())
+    }
+
+    /// Returns the file info of the damaged file.
+    #[must_use]
+    pub const fn info(&self) -> SnowBinInfo {
+        self.info
+    }
+
+    /// Returns the metadata stored for the whole file, or empty metadata if it was damaged.
+    #[must_use]
+    pub const fn file_metadata(&self) -> &SnowBinMetadata {
+        &self.metadata
+    }
+
+    /// Returns every entry that was recovered, in file order.
+    #[must_use]
+    pub fn entries(&self) -> &[SnowBinSalvagedEntry] {
+        &self.entries
+    }
+
+    /// Returns the headers of entries whose framing was intact, but whose data did not match their
+    /// outboard hashes or whose metadata was malformed.
+    #[must_use]
+    pub fn damaged(&self) -> &[String] {
+        &self.damaged
+    }
+
+    /// Returns the offset of the entry where walking stopped, or of the end header if every entry
+    /// was walked.
+    #[must_use]
+    pub const fn stopped_at(&self) -> u64 {
+        self.stopped_at
+    }
+
+    /// Returns why walking stopped before the end header, or `None` if the end header was reached.
+    #[must_use]
+    pub const fn error(&self) -> Option<&SnowBinError> {
+        self.error.as_ref()
+    }
+
+    /// Returns true if the end header was reached and the hash after it matches.
+    #[must_use]
+    pub const fn hash_matches(&self) -> bool {
+        self.hash_matches
+    }
+
+    /// Returns true if nothing was lost: the end header was reached, the hash matches, and no
+    /// entry was damaged.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.error.is_none() && self.hash_matches && self.damaged.is_empty()
+    }
+
+    /// Encodes the recovered entries into a new, valid file with the same file info.
+    /// # Errors
+    /// Returns `SnowBinError` if an entry could not be encoded.
+    pub fn encode(&self) -> Result<Vec<u8>, SnowBinError> {
+        let mut encoder = SnowBinEncoder::with_metadata(self.info, &self.metadata)?;
+        for entry in &self.entries {
+            encoder.write_with_metadata(&entry.header, &entry.data, &entry.metadata)?;
+        }
+        encoder.finish()
+    }
+
+    /// Writes the recovered entries to a new, valid file at `path` with the same file info.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be created or opened, or the file cannot be
+    /// written to.
+    #[cfg(feature = "std")]
+    pub fn write_file(&self, path: PathBuf) -> Result<SnowBinWriteSummary, SnowBinError> {
+        let mut writer = SnowBinWriter::with_metadata(self.info, path, &self.metadata)?;
+        for entry in &self.entries {
+            writer.write_with_metadata(&entry.header, &entry.data, &entry.metadata)?;
+        }
+        writer.finish()
+    }
+}
@@ -0,0 +1,416 @@
+use alloc::{collections::BTreeSet, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{
+    error::SnowBinError,
+    format::Walker,
+    limits::SnowBinLimits,
+    probe::probe,
+    reader::{self, Input},
+    SnowBinInfo,
+};
+
+/// What the data of an entry holds, checked by `SnowBinSchema`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SnowBinValueType {
+    /// Any bytes.
+    Bytes,
+    /// UTF-8 text.
+    Utf8,
+    /// A little endian `u8`.
+    U8,
+    /// A little endian `u16`.
+    U16,
+    /// A little endian `u32`.
+    U32,
+    /// A little endian `u64`.
+    U64,
+    /// A little endian `i8`.
+    I8,
+    /// A little endian `i16`.
+    I16,
+    /// A little endian `i32`.
+    I32,
+    /// A little endian `i64`.
+    I64,
+    /// A little endian `f32`.
+    F32,
+    /// A little endian `f64`.
+    F64,
+    /// A nested `SnowBinary` file with a Spec version this library can read, see `probe`.
+    SnowBinary,
+}
+
+impl SnowBinValueType {
+    /// Returns the length of the data in bytes if values of this type have a fixed length.
+    #[must_use]
+    pub const fn width(&self) -> Option<u64> {
+        match self {
+            Self::U8 | Self::I8 => Some(1),
+            Self::U16 | Self::I16 => Some(2),
+            Self::U32 | Self::I32 | Self::F32 => Some(4),
+            Self::U64 | Self::I64 | Self::F64 => Some(8),
+            Self::Bytes | Self::Utf8 | Self::SnowBinary => None,
+        }
+    }
+
+    /// Returns true if the data has to be read to check the type.
+    const fn needs_data(self) -> bool {
+        matches!(self, Self::Utf8 | Self::SnowBinary)
+    }
+
+    /// Returns true if `data` holds a value of this type.
+    fn matches(self, data: &[u8]) -> bool {
+        match self {
+            Self::Utf8 => core::str::from_utf8(data).is_ok(),
+            Self::SnowBinary => probe(data).is_supported(),
+            _ => self.width().is_none_or(|width| data.len() as u64 == width),
+        }
+    }
+}
+
+impl fmt::Display for SnowBinValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Bytes => "bytes",
+            Self::Utf8 => "UTF-8 text",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+            Self::SnowBinary => "a SnowBinary file",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What `SnowBinSchema` expects of one header.
+///
+/// Default returns a `SnowBinField` for any bytes of any length.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SnowBinField {
+    value_type: SnowBinValueType,
+    min_size: u64,
+    max_size: u64,
+}
+
+impl SnowBinField {
+    /// Creates a new `SnowBinField` for values of `value_type`. Fixed length types also limit the
+    /// length of the data to their width.
+    #[must_use]
+    pub const fn new(value_type: SnowBinValueType) -> Self {
+        Self {
+            value_type,
+            min_size: 0,
+            max_size: u64::MAX,
+        }
+    }
+
+    /// Sets the least number of bytes the data may have.
+    #[must_use]
+    pub const fn min_size(mut self, min_size: u64) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Sets the most bytes the data may have.
+    #[must_use]
+    pub const fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Returns the type of the values.
+    #[must_use]
+    pub const fn value_type(&self) -> SnowBinValueType {
+        self.value_type
+    }
+
+    /// Returns the least and most bytes the data may have, including the width of the type.
+    #[must_use]
+    pub const fn size_range(&self) -> (u64, u64) {
+        match self.value_type.width() {
+            Some(width) => (width, width),
+            None => (self.min_size, self.max_size),
+        }
+    }
+}
+
+impl Default for SnowBinField {
+    fn default() -> Self {
+        Self::new(SnowBinValueType::Bytes)
+    }
+}
+
+/// Something in a file that does not match a `SnowBinSchema`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum SnowBinViolation {
+    /// A required header is not in the file.
+    Missing {
+        /// The required header.
+        header: String,
+    },
+    /// A header is not in the schema, which denies unknown headers.
+    Unexpected {
+        /// The unknown header.
+        header: String,
+    },
+    /// The data of a header is shorter or longer than allowed.
+    SizeOutOfRange {
+        /// The header of the entry.
+        header: String,
+        /// Length of the entry's data.
+        size: u64,
+        /// Least number of bytes allowed.
+        min: u64,
+        /// Most bytes allowed.
+        max: u64,
+    },
+    /// The data of a header does not hold a value of the expected type.
+    WrongType {
+        /// The header of the entry.
+        header: String,
+        /// The type the schema expects.
+        expected: SnowBinValueType,
+    },
+}
+
+impl SnowBinViolation {
+    /// Returns the header the violation is about.
+    #[must_use]
+    pub fn header(&self) -> &str {
+        match self {
+            Self::Missing { header }
+            | Self::Unexpected { header }
+            | Self::SizeOutOfRange { header, .. }
+            | Self::WrongType { header, .. } => header,
+        }
+    }
+}
+
+impl fmt::Display for SnowBinViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { header } => write!(f, "Required header \"{header}\" is missing."),
+            Self::Unexpected { header } => {
+                write!(f, "Header \"{header}\" is not in the schema.")
+            }
+            Self::SizeOutOfRange {
+                header,
+                size,
+                min,
+                max,
+            } => write!(
+                f,
+                "Header \"{header}\" has {size} bytes of data, expected {min} to {max}."
+            ),
+            Self::WrongType { header, expected } => {
+                write!(f, "Header \"{header}\" does not hold {expected}.")
+            }
+        }
+    }
+}
+
+/// Lists the headers a file is expected to have and what their data should look like.
+///
+/// Checking a file against a schema reports every violation at once, instead of failing on the
+/// first `read` of a missing header. Use `SnowBinReader::validate` or `SnowBinDecoder::validate`
+/// when opening a file, and `validate_entry` or `SnowBinStagedWriter::validate` when writing one.
+/// Only the first entry of a header is checked, like `read` does.
+/// # Example
+/// ```
+/// use snowbinary::{
+///     SnowBinDecoder, SnowBinEncoder, SnowBinField, SnowBinInfo, SnowBinSchema, SnowBinValueType,
+///     SnowBinViolation,
+/// };
+///
+/// let schema = SnowBinSchema::new()
+///     .required("name", SnowBinField::new(SnowBinValueType::Utf8).max_size(64))
+///     .required("version", SnowBinField::new(SnowBinValueType::U32))
+///     .optional("icon", SnowBinField::default())
+///     .deny_unknown();
+///
+/// let mut encoder = SnowBinEncoder::new(SnowBinInfo::default()).unwrap();
+/// encoder.write("name", b"Snow").unwrap();
+/// encoder.write("version", &1_u64.to_le_bytes()).unwrap();
+/// encoder.write("extra", b"?").unwrap();
+/// let bytes = encoder.finish().unwrap();
+///
+/// let violations = SnowBinDecoder::new(&bytes).unwrap().validate(&schema).unwrap();
+/// assert_eq!(
+///     violations,
+///     [
+///         SnowBinViolation::SizeOutOfRange {
+///             header: String::from("version"),
+///             size: 8,
+///             min: 4,
+///             max: 4
+///         },
+///         SnowBinViolation::Unexpected {
+///             header: String::from("extra")
+///         },
+///     ]
+/// );
+/// ```
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct SnowBinSchema {
+    fields: Vec<(String, SnowBinField, bool)>,
+    deny_unknown: bool,
+}
+
+impl SnowBinSchema {
+    /// Creates a new empty `SnowBinSchema`, which allows any headers.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            fields: Vec::new(),
+            deny_unknown: false,
+        }
+    }
+
+    /// Expects `header` to be in the file, holding `field`.
+    #[must_use]
+    pub fn required(mut self, header: &str, field: SnowBinField) -> Self {
+        self.fields.push((String::from(header), field, true));
+        self
+    }
+
+    /// Allows `header` to be missing, but if it is in the file it must hold `field`.
+    #[must_use]
+    pub fn optional(mut self, header: &str, field: SnowBinField) -> Self {
+        self.fields.push((String::from(header), field, false));
+        self
+    }
+
+    /// Reports headers that are not in the schema as `Unexpected`.
+    #[must_use]
+    pub const fn deny_unknown(mut self) -> Self {
+        self.deny_unknown = true;
+        self
+    }
+
+    fn field(&self, header: &str) -> Option<SnowBinField> {
+        self.fields
+            .iter()
+            .find(|(name, _, _)| name == header)
+            .map(|&(_, field, _)| field)
+    }
+
+    /// Checks one entry before it is written, without checking for missing headers.
+    #[must_use]
+    pub fn validate_entry(&self, header: &str, data: &[u8]) -> Vec<SnowBinViolation> {
+        let mut violations = Vec::new();
+        self.check(header, data.len() as u64, Some(data), &mut violations);
+        violations
+    }
+
+    /// Checks an entry with `size` bytes of data. The type is only checked if `data` is given.
+    fn check(
+        &self,
+        header: &str,
+        size: u64,
+        data: Option<&[u8]>,
+        violations: &mut Vec<SnowBinViolation>,
+    ) {
+        let Some(field) = self.field(header)
+        else {
+            if self.deny_unknown {
+                violations.push(SnowBinViolation::Unexpected {
+                    header: String::from(header),
+                });
+            }
+            return;
+        };
+
+        let (min, max) = field.size_range();
+        if !(min..=max).contains(&size) {
+            violations.push(SnowBinViolation::SizeOutOfRange {
+                header: String::from(header),
+                size,
+                min,
+                max,
+            });
+        }
+        else if data.is_some_and(|data| !field.value_type.matches(data)) {
+            violations.push(SnowBinViolation::WrongType {
+                header: String::from(header),
+                expected: field.value_type,
+            });
+        }
+    }
+
+    /// Reports every required header that is not in `seen`.
+    fn check_missing(&self, seen: &BTreeSet<&str>, violations: &mut Vec<SnowBinViolation>) {
+        for (header, _, required) in &self.fields {
+            if *required && !seen.contains(header.as_str()) {
+                violations.push(SnowBinViolation::Missing {
+                    header: header.clone(),
+                });
+            }
+        }
+    }
+
+    /// Checks entries that are all in memory, such as staged ones.
+    pub(crate) fn validate_all<'a>(
+        &self,
+        entries: impl Iterator<Item = (&'a str, &'a [u8])>,
+    ) -> Vec<SnowBinViolation> {
+        let mut seen = BTreeSet::new();
+        let mut violations = Vec::new();
+        for (header, data) in entries {
+            if seen.insert(header) {
+                self.check(header, data.len() as u64, Some(data), &mut violations);
+            }
+        }
+        self.check_missing(&seen, &mut violations);
+        violations
+    }
+}
+
+/// Checks every entry of a file against `schema`, only reading the data of entries whose type
+/// needs it.
+pub fn validate<I: Input>(
+    input: &mut I,
+    info: SnowBinInfo,
+    limits: SnowBinLimits,
+    schema: &SnowBinSchema,
+) -> Result<Vec<SnowBinViolation>, SnowBinError> {
+    let mut entries = Vec::new();
+    let mut walker = Walker::new(input, info, limits)?;
+    while let Some(entry) = walker.next(input)? {
+        entries.push(entry);
+    }
+
+    let mut seen = BTreeSet::new();
+    let mut violations = Vec::new();
+    for entry in &entries {
+        let header = entry.name(info);
+        if !seen.insert(header) {
+            continue;
+        }
+
+        let needs_data = schema.field(header).is_some_and(|field| {
+            let (min, max) = field.size_range();
+            field.value_type.needs_data() && (min..=max).contains(&entry.size)
+        });
+        let data = if needs_data {
+            input.seek(entry.offset)?;
+            Some(reader::read_bytes(input, entry.size).map_err(|e| e.with_header(header))?)
+        }
+        else {
+            None
+        };
+        schema.check(header, entry.size, data.as_deref(), &mut violations);
+    }
+    schema.check_missing(&seen, &mut violations);
+
+    Ok(violations)
+}
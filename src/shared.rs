@@ -0,0 +1,225 @@
+use std::{fs::File, ops::Range, path::PathBuf, sync::Arc};
+
+use crate::{
+    error::SnowBinError,
+    format,
+    limits::SnowBinLimits,
+    metadata::SnowBinMetadata,
+    options::{SnowBinDuplicates, SnowBinOpenOptions, SnowBinVerification},
+    outboard::{self, Outboard},
+    reader::{self, AtInput, Input},
+    SnowBinInfo,
+};
+
+/// Allows reading from a `SnowBinary` file from many threads at once.
+///
+/// Unlike `SnowBinReader`, lookups take `&self` and use positional reads that do not share a file
+/// cursor, so a `SnowBinSharedReader` can be put in an `Arc` and read from without locking.
+/// # Example
+/// ```
+/// use std::{path::PathBuf, sync::Arc, thread};
+///
+/// use snowbinary::{SnowBinInfo, SnowBinSharedReader, SnowBinWriter};
+///
+/// {
+///     let mut writer =
+///         SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("shared.temp")).unwrap();
+///     writer.write("Header", b"This is data!").unwrap();
+/// }
+///
+/// let reader = Arc::new(SnowBinSharedReader::new(PathBuf::from("shared.temp")).unwrap());
+/// let workers = (0..4)
+///     .map(|_| {
+///         let reader = Arc::clone(&reader);
+///         thread::spawn(move || reader.read("Header").unwrap())
+///     })
+///     .collect::<Vec<_>>();
+/// for worker in workers {
+///     assert_eq!(worker.join().unwrap(), b"This is data!");
+/// }
+/// # std::fs::remove_file("shared.temp").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct SnowBinSharedReader {
+    file: Arc<File>,
+    base: u64,
+    len: u64,
+    info: SnowBinInfo,
+    options: SnowBinOpenOptions,
+    metadata: SnowBinMetadata,
+}
+
+impl SnowBinSharedReader {
+    /// Creates a new `SnowBinSharedReader`. Params are pulled from the file info.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be opened, the file cannot be read from, or
+    /// the hash does not match.
+    pub fn new(path: PathBuf) -> Result<Self, SnowBinError> {
+        SnowBinOpenOptions::new().open_shared(path)
+    }
+
+    /// Creates a new `SnowBinSharedReader` that enforces `limits` while reading.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be opened, the file cannot be read from, the
+    /// hash does not match, or the file exceeds `limits`.
+    pub fn with_limits(path: PathBuf, limits: SnowBinLimits) -> Result<Self, SnowBinError> {
+        SnowBinOpenOptions::new().limits(limits).open_shared(path)
+    }
+
+    /// Creates a new `SnowBinSharedReader` without hashing the whole file first.
+    /// See `SnowBinReader::unverified`.
+    /// # Errors
+    /// Returns `SnowBinError` if the file could not be opened, the file cannot be read from, or the
+    /// file info exceeds `limits`.
+    pub fn unverified(path: PathBuf, limits: SnowBinLimits) -> Result<Self, SnowBinError> {
+        SnowBinOpenOptions::new()
+            .verification(SnowBinVerification::Skip)
+            .limits(limits)
+            .open_shared(path)
+    }
+
+    pub(crate) fn open_with(
+        path: PathBuf,
+        options: SnowBinOpenOptions,
+    ) -> Result<Self, SnowBinError> {
+        let file = Self::open(path)?;
+        let len = Self::file_len(&file)?;
+        Self::from_file(Arc::new(file), 0, len, options)
+    }
+
+    fn open(path: PathBuf) -> Result<File, SnowBinError> {
+        File::open(&path).map_err(|source| SnowBinError::CouldNotCreateOrOpenFile { path, source })
+    }
+
+    fn file_len(file: &File) -> Result<u64, SnowBinError> {
+        file.metadata()
+            .map(|metadata| metadata.len())
+            .map_err(|source| SnowBinError::IOReadError {
+                offset: 0,
+                header: None,
+                source,
+            })
+    }
+
+    fn from_file(
+        file: Arc<File>,
+        base: u64,
+        len: u64,
+        options: SnowBinOpenOptions,
+    ) -> Result<Self, SnowBinError> {
+        let limits = options.limits;
+        let mut input = AtInput::new(&file, base, len);
+        if options.verification == SnowBinVerification::Full {
+            Self::verify(&mut input, limits)?;
+        }
+        let info = format::read_info(&mut input, limits)?;
+        let metadata = format::read_file_metadata(&mut input, info, limits)?;
+        if options.duplicates == SnowBinDuplicates::Reject {
+            format::check_duplicates(&mut input, info, limits)?;
+        }
+
+        Ok(Self {
+            file,
+            base,
+            len,
+            info,
+            options,
+            metadata,
+        })
+    }
+
+    fn verify(input: &mut AtInput<'_>, limits: SnowBinLimits) -> Result<(), SnowBinError> {
+        #[cfg(feature = "parallel")]
+        if let Some(map) = crate::file::map(input.file(), input.absolute(0), input.len()?)? {
+            return format::verify_slice(&map, limits);
+        }
+
+        format::verify(input, limits)
+    }
+
+    fn input(&self) -> AtInput<'_> {
+        AtInput::new(&self.file, self.base, self.len)
+    }
+
+    /// Returns the metadata stored for the whole file.
+    #[must_use]
+    pub const fn file_metadata(&self) -> &SnowBinMetadata {
+        &self.metadata
+    }
+
+    /// Reads data from the file using the header.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, the end of the file was reached, or
+    /// an entry exceeds the reader's limits.
+    pub fn read(&self, header: &str) -> Result<Vec<u8>, SnowBinError> {
+        let mut input = self.input();
+        if self.options.verification == SnowBinVerification::Skip && self.info.outboard() {
+            let entry = format::find_entry(&mut input, self.info, self.options.limits, header)?;
+            let outboard = Outboard::read(&mut input, &entry).map_err(|e| e.with_header(header))?;
+            return outboard::read_range(&mut input, &entry, Some(&outboard), 0, entry.size)
+                .map_err(|e| e.with_header(header));
+        }
+
+        let size = format::find(&mut input, self.info, self.options.limits, header)?;
+        reader::read_bytes(&mut input, size).map_err(|e| e.with_header(header))
+    }
+
+    /// Reads `range` of the data of the entry with `header`. See `SnowBinReader::read_range`.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, the end of the file was reached,
+    /// `range` is outside of the entry, or the data does not match its outboard hashes.
+    pub fn read_range(&self, header: &str, range: Range<u64>) -> Result<Vec<u8>, SnowBinError> {
+        let mut input = self.input();
+        let entry = format::find_entry(&mut input, self.info, self.options.limits, header)?;
+        let outboard = if self.info.outboard() {
+            Some(Outboard::read(&mut input, &entry).map_err(|e| e.with_header(header))?)
+        }
+        else {
+            None
+        };
+
+        outboard::read_range(
+            &mut input,
+            &entry,
+            outboard.as_ref(),
+            range.start,
+            range.end,
+        )
+        .map_err(|e| e.with_header(header))
+    }
+
+    /// Reads the metadata of an entry without reading its data.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, the end of the file was reached, the
+    /// metadata is malformed, or an entry exceeds the reader's limits.
+    pub fn metadata(&self, header: &str) -> Result<SnowBinMetadata, SnowBinError> {
+        format::metadata(&mut self.input(), self.info, self.options.limits, header)
+    }
+
+    /// Opens the entry with `header` as a `SnowBinary` file of its own, sharing the open file.
+    /// See `SnowBinReader::open_nested`.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from, the end of the file was reached, or
+    /// the entry is not a valid `SnowBinary` file.
+    pub fn open_nested(&self, header: &str) -> Result<Self, SnowBinError> {
+        let mut input = self.input();
+        let size = format::find(&mut input, self.info, self.options.limits, header)?;
+        let base = self.base + input.position();
+
+        Self::from_file(Arc::clone(&self.file), base, size, self.options)
+    }
+
+    /// Lists every header in the file, in the order they were written.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from or an entry exceeds the reader's limits.
+    pub fn headers(&self) -> Result<Vec<String>, SnowBinError> {
+        format::headers(&mut self.input(), self.info, self.options.limits)
+    }
+
+    /// Lists the paths of the direct children of `prefix`. See `SnowBinReader::children`.
+    /// # Errors
+    /// Returns `SnowBinError` if the file cannot be read from or an entry exceeds the reader's limits.
+    pub fn children(&self, prefix: &str) -> Result<Vec<String>, SnowBinError> {
+        format::children(&mut self.input(), self.info, self.options.limits, prefix)
+    }
+}
@@ -0,0 +1,28 @@
+/// What was written by a `SnowBinWriter`, returned by `SnowBinWriter::finish`.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SnowBinWriteSummary {
+    pub(crate) hash: [u8; 32],
+    pub(crate) entries: u64,
+    pub(crate) size: u64,
+}
+
+impl SnowBinWriteSummary {
+    /// Returns the blake3 hash stored at the end of the file.
+    #[must_use]
+    pub const fn hash(&self) -> [u8; 32] {
+        self.hash
+    }
+
+    /// Returns the number of entries written, not counting entries of nested files.
+    #[must_use]
+    pub const fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Returns the size of the file in bytes.
+    #[must_use]
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+}
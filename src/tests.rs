@@ -1,7 +1,18 @@
 mod default_tests {
-    use std::path::PathBuf;
+    use std::{
+        fs::File,
+        io::{ErrorKind, Read},
+        path::PathBuf,
+        sync::Arc,
+    };
 
-    use crate::{SnowBinError, SnowBinInfo, SnowBinReader, SnowBinWriter};
+    use crate::{
+        SnowBinChange, SnowBinDecoder, SnowBinDiff, SnowBinDiffLine, SnowBinDropAction,
+        SnowBinDuplicates, SnowBinEncoder, SnowBinError, SnowBinField, SnowBinInfo, SnowBinLimit,
+        SnowBinLimits, SnowBinMap, SnowBinMetadata, SnowBinOpenOptions, SnowBinPlan, SnowBinProbe,
+        SnowBinReader, SnowBinSalvage, SnowBinSchema, SnowBinSharedReader, SnowBinStagedWriter,
+        SnowBinValueType, SnowBinVerification, SnowBinViolation, SnowBinWriter,
+    };
 
     #[test]
     fn info_test() -> Result<(), SnowBinError> {
@@ -20,34 +31,34 @@ mod default_tests {
         SnowBinInfo::new(u32::MAX, 32)?;
         SnowBinInfo::new(u32::MAX, 64)?;
 
-        assert_eq!(
+        assert!(matches!(
             SnowBinInfo::new(1, 8).unwrap_err(),
             SnowBinError::HeaderSizeTooSmall
-        );
-        assert_eq!(
+        ));
+        assert!(matches!(
             SnowBinInfo::new(1, 16).unwrap_err(),
             SnowBinError::HeaderSizeTooSmall
-        );
-        assert_eq!(
+        ));
+        assert!(matches!(
             SnowBinInfo::new(1, 32).unwrap_err(),
             SnowBinError::HeaderSizeTooSmall
-        );
-        assert_eq!(
+        ));
+        assert!(matches!(
             SnowBinInfo::new(1, 64).unwrap_err(),
             SnowBinError::HeaderSizeTooSmall
-        );
-        assert_eq!(
+        ));
+        assert!(matches!(
             SnowBinInfo::new(1, 1).unwrap_err(),
             SnowBinError::HeaderSizeTooSmall
-        );
-        assert_eq!(
+        ));
+        assert!(matches!(
             SnowBinInfo::new(8, 1).unwrap_err(),
             SnowBinError::DataSizeNotAllowed
-        );
-        assert_eq!(
+        ));
+        assert!(matches!(
             SnowBinInfo::new(8, u8::MAX).unwrap_err(),
             SnowBinError::DataSizeNotAllowed
-        );
+        ));
 
         Ok(())
     }
@@ -69,12 +80,1646 @@ mod default_tests {
             let mut reader = SnowBinReader::new(PathBuf::from("./file.temp"))?;
 
             reader.read("TEST").unwrap();
-            assert_eq!(
+            assert!(matches!(
                 reader.read("NULL_NO").unwrap_err(),
                 SnowBinError::ReachedEOF
-            );
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn io_error_test() {
+        use std::error::Error;
+
+        let err = SnowBinReader::new(PathBuf::from("./does_not_exist.temp")).unwrap_err();
+        match &err {
+            SnowBinError::CouldNotCreateOrOpenFile { path, source } => {
+                assert_eq!(path, &PathBuf::from("./does_not_exist.temp"));
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+            }
+            _ => panic!("unexpected error: {err:?}"),
+        }
+        assert!(err.source().is_some());
+        assert!(err.to_string().contains("does_not_exist.temp"));
+    }
+
+    #[test]
+    fn memory_test() -> Result<(), SnowBinError> {
+        let mut encoder = SnowBinEncoder::new(SnowBinInfo::new(8, 16)?)?;
+        encoder.write("TEST", b"This is a String!")?;
+        encoder.write("EMPTY", b"")?;
+        let bytes = encoder.finish()?;
+
+        let decoder = SnowBinDecoder::new(&bytes)?;
+        assert_eq!(decoder.read("TEST")?, b"This is a String!");
+        assert_eq!(decoder.read("EMPTY")?, b"");
+        assert!(matches!(
+            decoder.read("NULL_NO").unwrap_err(),
+            SnowBinError::ReachedEOF
+        ));
+
+        assert!(matches!(
+            SnowBinDecoder::new(&bytes[..16]).unwrap_err(),
+            SnowBinError::UnexpectedEof { .. }
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn limits_test() -> Result<(), SnowBinError> {
+        let mut encoder = SnowBinEncoder::new(SnowBinInfo::new(16, 64)?)?;
+        encoder.write("SMALL", b"1234")?;
+        encoder.write("LARGE", &[0_u8; 256])?;
+        let bytes = encoder.finish()?;
+
+        let limits = SnowBinLimits::new().max_entry_size(16);
+        let decoder = SnowBinDecoder::with_limits(&bytes, limits)?;
+        assert_eq!(decoder.read("SMALL")?, b"1234");
+        assert!(matches!(
+            decoder.read("LARGE").unwrap_err(),
+            SnowBinError::LimitExceeded {
+                limit: SnowBinLimit::EntrySize,
+                value: 256,
+                max: 16
+            }
+        ));
+
+        let limits = SnowBinLimits::new().max_entries(1);
+        let decoder = SnowBinDecoder::with_limits(&bytes, limits)?;
+        assert!(matches!(
+            decoder.read("LARGE").unwrap_err(),
+            SnowBinError::LimitExceeded {
+                limit: SnowBinLimit::Entries,
+                ..
+            }
+        ));
+
+        let limits = SnowBinLimits::new().max_header_size(8);
+        assert!(matches!(
+            SnowBinDecoder::with_limits(&bytes, limits).unwrap_err(),
+            SnowBinError::LimitExceeded {
+                limit: SnowBinLimit::HeaderSize,
+                ..
+            }
+        ));
+
+        let limits = SnowBinLimits::new().max_total_bytes(64);
+        assert!(matches!(
+            SnowBinDecoder::with_limits(&bytes, limits).unwrap_err(),
+            SnowBinError::LimitExceeded {
+                limit: SnowBinLimit::TotalBytes,
+                ..
+            }
+        ));
+
+        // The total size is checked even when the file is not verified.
+        std::fs::write("./total.temp", &bytes).unwrap();
+        assert!(matches!(
+            SnowBinOpenOptions::new()
+                .verification(SnowBinVerification::Skip)
+                .limits(limits)
+                .open(PathBuf::from("./total.temp")),
+            Err(SnowBinError::LimitExceeded {
+                limit: SnowBinLimit::TotalBytes,
+                ..
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_test() -> Result<(), SnowBinError> {
+        use std::io::Cursor;
+
+        use crate::reader::{self, IoInput};
+
+        let mut input = IoInput::new(Cursor::new(vec![1_u8, 2, 3]));
+        assert!(matches!(
+            reader::read_u64(&mut input).unwrap_err(),
+            SnowBinError::UnexpectedEof {
+                offset: 0,
+                expected: 8,
+                actual: 3
+            }
+        ));
+
+        let mut input = IoInput::new(Cursor::new(vec![1_u8, 2, 3, 4, 5]));
+        assert_eq!(reader::read_u16(&mut input)?, 0x0201);
+        assert!(matches!(
+            reader::read_bytes(&mut input, 4).unwrap_err(),
+            SnowBinError::UnexpectedEof {
+                offset: 2,
+                expected: 4,
+                actual: 3
+            }
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn variable_headers_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::new(1024, 64)?.with_variable_headers();
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./variable.temp"))?;
+
+            writer.write("KEY", b"no space")?;
+            writer.write("KEY ", b"one space")?;
+            writer.write("SNOW_END_NOT", b"not the end")?;
+            writer.write("textures/ui/a_very_long_header_name", b"long")?;
+            writer.write("", b"empty")?;
+
+            writer.close()?;
+        }
+
+        {
+            let mut reader = SnowBinReader::new(PathBuf::from("./variable.temp"))?;
+
+            assert_eq!(reader.read("KEY")?, b"no space");
+            assert_eq!(reader.read("KEY ")?, b"one space");
+            assert_eq!(reader.read("SNOW_END_NOT")?, b"not the end");
+            assert_eq!(reader.read("textures/ui/a_very_long_header_name")?, b"long");
+            assert_eq!(reader.read("")?, b"empty");
+            assert!(matches!(
+                reader.read("KEY  ").unwrap_err(),
+                SnowBinError::ReachedEOF
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn varint_sizes_test() -> Result<(), SnowBinError> {
+        use crate::{reader, writer};
+
+        for value in [0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let (buffer, len) = writer::encode_varint(value);
+            let mut input = reader::SliceInput::new(&buffer[..len]);
+            assert_eq!(reader::read_varint(&mut input)?, value);
+        }
+        let mut input = reader::SliceInput::new(&[0xff; 10]);
+        assert!(matches!(
+            reader::read_varint(&mut input).unwrap_err(),
+            SnowBinError::MalformedUInt
+        ));
+
+        let info = SnowBinInfo::new(8, 8)?.with_varint_sizes();
+        let mut encoder = SnowBinEncoder::new(info)?;
+        encoder.write("SMALL", b"1")?;
+        encoder.write("LARGE", &[7_u8; 1000])?;
+        let bytes = encoder.finish()?;
+
+        let decoder = SnowBinDecoder::new(&bytes)?;
+        assert_eq!(decoder.read("SMALL")?, b"1");
+        assert_eq!(decoder.read("LARGE")?, &[7_u8; 1000][..]);
+        assert_eq!(bytes.len(), 25 + (8 + 1 + 1) + (8 + 2 + 1000) + 8 + 32);
+
+        Ok(())
+    }
+
+    #[test]
+    fn alignment_test() -> Result<(), SnowBinError> {
+        assert!(matches!(
+            SnowBinInfo::default().with_alignment(3).unwrap_err(),
+            SnowBinError::AlignmentNotAllowed
+        ));
+        assert_eq!(
+            SnowBinInfo::default().with_alignment(1)?,
+            SnowBinInfo::default()
+        );
+
+        for alignment in [8, 16, 64, 4096] {
+            let info = SnowBinInfo::new(16, 8)?
+                .with_varint_sizes()
+                .with_alignment(alignment)?;
+            assert_eq!(info.alignment(), alignment);
+
+            let mut encoder = SnowBinEncoder::new(info)?;
+            encoder.write("ONE", b"1")?;
+            encoder.write("FLOATS", &[0_u8; 12])?;
+            encoder.write("EMPTY", b"")?;
+            let bytes = encoder.finish()?;
+
+            let decoder = SnowBinDecoder::new(&bytes)?;
+            for (header, len) in [("ONE", 1), ("FLOATS", 12), ("EMPTY", 0)] {
+                let data = decoder.read(header)?;
+                assert_eq!(data.len(), len);
+                let offset = data.as_ptr() as usize - bytes.as_ptr() as usize;
+                assert_eq!(offset % alignment as usize, 0);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn path_test() -> Result<(), SnowBinError> {
+        use crate::path;
+
+        assert!(path::in_subtree("a/b/c", "a/b", '/'));
+        assert!(path::in_subtree("a/b", "a/b/", '/'));
+        assert!(!path::in_subtree("a/bc", "a/b", '/'));
+        assert_eq!(path::child("a/b/c", "a", '/'), Some("a/b"));
+        assert_eq!(path::child("a/b/c", "", '/'), Some("a"));
+        assert_eq!(path::child("a/b", "a/b", '/'), None);
+        assert_eq!(path::child("ab/c", "a", '/'), None);
+
+        let info = SnowBinInfo::new(64, 64)?
+            .with_variable_headers()
+            .with_separator('/')?;
+        assert_eq!(info.separator(), Some('/'));
+        assert!(matches!(
+            SnowBinInfo::default().with_separator('a').unwrap_err(),
+            SnowBinError::SeparatorNotAllowed
+        ));
+
+        let mut encoder = SnowBinEncoder::new(info)?;
+        encoder.write("textures/ui/button", b"button")?;
+        encoder.write("sounds/ui/click", b"click")?;
+        encoder.write("textures/ui/panel", b"panel")?;
+        encoder.write("textures/bg", b"bg")?;
+        assert!(matches!(
+            encoder.write("textures//bad", b"").unwrap_err(),
+            SnowBinError::InvalidPath
+        ));
+        let bytes = encoder.finish()?;
+
+        let decoder = SnowBinDecoder::new(&bytes)?;
+        assert_eq!(decoder.children("")?, ["textures", "sounds"]);
+        assert_eq!(
+            decoder.children("textures")?,
+            ["textures/ui", "textures/bg"]
+        );
+        let subtree = decoder
+            .subtree("textures/ui")?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            subtree,
+            [
+                (String::from("textures/ui/button"), &b"button"[..]),
+                (String::from("textures/ui/panel"), &b"panel"[..]),
+            ]
+        );
+
+        std::fs::write("./path.temp", &bytes).unwrap();
+        let mut reader = SnowBinReader::new(PathBuf::from("./path.temp"))?;
+        assert_eq!(reader.headers()?.len(), 4);
+        let subtree = reader.subtree("sounds")?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            subtree,
+            [(String::from("sounds/ui/click"), b"click".to_vec())]
+        );
+        assert_eq!(reader.read("textures/bg")?, b"bg");
+
+        // File metadata over the limits fails instead of looking like an empty subtree.
+        let metadata = SnowBinMetadata::new().with_description("A description");
+        let mut encoder = SnowBinEncoder::with_metadata(info.with_file_metadata(), &metadata)?;
+        encoder.write("textures/ui", b"ui")?;
+        let bytes = encoder.finish()?;
+        let limits = SnowBinLimits::new().max_metadata_size(4);
+        assert!(matches!(
+            SnowBinDecoder::with_limits(&bytes, limits)?.subtree("textures"),
+            Err(SnowBinError::LimitExceeded {
+                limit: SnowBinLimit::MetadataSize,
+                ..
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn nested_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::new(16, 32)?;
+        let child_info = SnowBinInfo::new(8, 8)?.with_varint_sizes();
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./nested.temp"))?;
+            writer.write("BEFORE", b"before")?;
+
+            {
+                let mut bundle = writer.write_nested("BUNDLE", child_info)?;
+                bundle.write("CODE", b"print()")?;
+                {
+                    let mut inner = bundle.write_nested("INNER", SnowBinInfo::default())?;
+                    inner.write("DEEP", &[9_u8; 300])?;
+                    inner.close()?;
+                }
+                bundle.close()?;
+                assert!(matches!(
+                    bundle.close().unwrap_err(),
+                    SnowBinError::IOWriterClosed
+                ));
+            }
+
+            writer.write("AFTER", b"after")?;
+            writer.close()?;
+        }
+
+        {
+            let mut reader = SnowBinReader::new(PathBuf::from("./nested.temp"))?;
+            assert_eq!(reader.read("AFTER")?, b"after");
+
+            let mut bundle = reader.open_nested("BUNDLE")?;
+            assert_eq!(bundle.read("CODE")?, b"print()");
+            let mut inner = bundle.open_nested("INNER")?;
+            assert_eq!(inner.read("DEEP")?, vec![9_u8; 300]);
+            assert!(matches!(
+                inner.read("BEFORE").unwrap_err(),
+                SnowBinError::ReachedEOF
+            ));
+            assert_eq!(reader.read("BEFORE")?, b"before");
+        }
+
+        let mut encoder = SnowBinEncoder::new(info)?;
+        {
+            let mut bundle = encoder.write_nested("BUNDLE", child_info)?;
+            bundle.write("CODE", b"print()")?;
+        }
+        encoder.write("AFTER", b"after")?;
+        let bytes = encoder.finish()?;
+
+        let decoder = SnowBinDecoder::new(&bytes)?;
+        assert_eq!(decoder.open_nested("BUNDLE")?.read("CODE")?, b"print()");
+        assert_eq!(decoder.read("AFTER")?, b"after");
+
+        // A nested file too long for the parent's data size is left out, and the parent goes on.
+        let small = SnowBinInfo::new(8, 8)?;
+        {
+            let options = SnowBinOpenOptions::new().duplicates(SnowBinDuplicates::Reject);
+            let mut writer = options.create(small, PathBuf::from("./nested_long.temp"))?;
+            writer.write("BEFORE", b"before")?;
+            {
+                let mut bundle = writer.write_nested("BUNDLE", SnowBinInfo::default())?;
+                bundle.write("BIG", &[1_u8; 300])?;
+                assert!(matches!(bundle.close(), Err(SnowBinError::DataTooLong)));
+            }
+            {
+                let mut bundle = writer.write_nested("BUNDLE", small)?;
+                {
+                    let mut inner = bundle.write_nested("INNER", SnowBinInfo::default())?;
+                    inner.write("BIG", &[2_u8; 300])?;
+                    assert!(matches!(inner.close(), Err(SnowBinError::DataTooLong)));
+                }
+                bundle.write("CODE", b"print()")?;
+            }
+            writer.write("AFTER", b"after")?;
+        }
+
+        let mut reader = SnowBinReader::new(PathBuf::from("./nested_long.temp"))?;
+        assert_eq!(reader.headers()?, ["BEFORE", "BUNDLE", "AFTER"]);
+        let mut bundle = reader.open_nested("BUNDLE")?;
+        assert_eq!(bundle.headers()?, ["CODE"]);
+        assert_eq!(reader.read("AFTER")?, b"after");
+
+        // A nested file that fails to close is left out as well.
+        let mut encoder = SnowBinEncoder::new(info)?;
+        {
+            let mut bundle = encoder.write_nested("BUNDLE", child_info)?;
+            bundle.write("CODE", b"print()")?;
+            std::mem::forget(bundle.write_nested("INNER", child_info)?);
+            assert!(matches!(
+                bundle.close(),
+                Err(SnowBinError::EntryNotFinished)
+            ));
+        }
+        encoder.write("AFTER", b"after")?;
+        let bytes = encoder.finish()?;
+        let decoder = SnowBinDecoder::new(&bytes)?;
+        assert_eq!(decoder.headers()?, ["AFTER"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn entry_metadata_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::default()
+            .with_entry_metadata()
+            .with_alignment(16)?;
+        let metadata = SnowBinMetadata::new()
+            .with_modified(1_700_000_000)
+            .with_content_type("text/plain")
+            .with("owner", "snow");
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./metadata.temp"))?;
+            writer.write_with_metadata("TEXT", b"hello", &metadata)?;
+            writer.write("PLAIN", b"plain")?;
+            {
+                let mut nested = writer.write_nested("NESTED", info)?;
+                nested.write_with_metadata("INNER", b"inner", &metadata)?;
+            }
+            writer.close()?;
+        }
+
+        let mut reader = SnowBinReader::new(PathBuf::from("./metadata.temp"))?;
+        let read = reader.metadata("TEXT")?;
+        assert_eq!(read, metadata);
+        assert_eq!(read.modified(), Some(1_700_000_000));
+        assert_eq!(read.content_type(), Some("text/plain"));
+        assert_eq!(read.get("owner"), Some(&b"snow"[..]));
+        assert!(reader.metadata("PLAIN")?.is_empty());
+        assert_eq!(reader.read("TEXT")?, b"hello");
+        assert_eq!(reader.read("PLAIN")?, b"plain");
+        assert_eq!(reader.open_nested("NESTED")?.metadata("INNER")?, metadata);
+
+        let limits = SnowBinLimits::new().max_metadata_size(8);
+        let mut reader = SnowBinReader::with_limits(PathBuf::from("./metadata.temp"), limits)?;
+        assert!(matches!(
+            reader.read("PLAIN").unwrap_err(),
+            SnowBinError::LimitExceeded {
+                limit: SnowBinLimit::MetadataSize,
+                ..
+            }
+        ));
+
+        let mut encoder = SnowBinEncoder::new(SnowBinInfo::default())?;
+        assert!(matches!(
+            encoder
+                .write_with_metadata("TEXT", b"hello", &metadata)
+                .unwrap_err(),
+            SnowBinError::MetadataNotEnabled
+        ));
+        encoder.write_with_metadata("TEXT", b"hello", &SnowBinMetadata::new())?;
+        let bytes = encoder.finish()?;
+        assert!(SnowBinDecoder::new(&bytes)?.metadata("TEXT")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_metadata_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::default()
+            .with_file_metadata()
+            .with_entry_metadata();
+        let metadata = SnowBinMetadata::new()
+            .with_application("snowbinary", "0.4.0")
+            .with_description("Test file")
+            .with_created(1_700_000_000);
+        {
+            let mut writer = SnowBinWriter::with_metadata(
+                info,
+                PathBuf::from("./file_metadata.temp"),
+                &metadata,
+            )?;
+            writer.write("DATA", b"data")?;
+        }
+
+        let mut reader = SnowBinReader::new(PathBuf::from("./file_metadata.temp"))?;
+        assert_eq!(reader.file_metadata(), &metadata);
+        assert_eq!(reader.file_metadata().application_version(), Some("0.4.0"));
+        assert_eq!(reader.file_metadata().created(), Some(1_700_000_000));
+        assert_eq!(reader.read("DATA")?, b"data");
+        assert_eq!(reader.headers()?, ["DATA"]);
+
+        let limits = SnowBinLimits::new().max_metadata_size(8);
+        assert!(matches!(
+            SnowBinReader::with_limits(PathBuf::from("./file_metadata.temp"), limits).unwrap_err(),
+            SnowBinError::LimitExceeded {
+                limit: SnowBinLimit::MetadataSize,
+                ..
+            }
+        ));
+
+        // Without timestamps, the same content is encoded to the same bytes.
+        let encode = |metadata: &SnowBinMetadata| -> Result<Vec<u8>, SnowBinError> {
+            let mut encoder = SnowBinEncoder::with_metadata(info, metadata)?;
+            encoder.write("DATA", b"data")?;
+            encoder.finish()
+        };
+        let first = encode(&metadata.clone().without_timestamps())?;
+        let second = encode(&metadata.clone().with_created(1).without_timestamps())?;
+        assert_eq!(first, second);
+        let decoder = SnowBinDecoder::new(&first)?;
+        assert_eq!(decoder.file_metadata()?.created(), None);
+        assert_eq!(decoder.file_metadata()?.description(), Some("Test file"));
+        assert_eq!(decoder.read("DATA")?, b"data");
+
+        assert!(matches!(
+            SnowBinEncoder::with_metadata(SnowBinInfo::default(), &metadata).unwrap_err(),
+            SnowBinError::MetadataNotEnabled
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn outboard_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::default().with_outboard();
+        let data = (0..100_000_u32)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+
+        // The stored entry hash matches a plain blake3 hash for any length.
+        for len in [0, 1, 16 * 1024, 16 * 1024 + 1, 48 * 1024, 100_000] {
+            let mut encoder = SnowBinEncoder::new(info)?;
+            encoder.write("DATA", &data[..len])?;
+            let bytes = encoder.finish()?;
+            let root = &bytes[bytes.len() - 72..bytes.len() - 40];
+            assert_eq!(root, blake3::hash(&data[..len]).as_bytes());
+            assert_eq!(SnowBinDecoder::new(&bytes)?.read("DATA")?, &data[..len]);
+        }
+
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./outboard.temp"))?;
+            writer.write("DATA", &data)?;
+            {
+                let mut nested = writer.write_nested("NESTED", SnowBinInfo::default())?;
+                nested.write("INNER", &data)?;
+            }
+            writer.write("SMALL", b"small")?;
+        }
+
+        let mut reader = SnowBinReader::new(PathBuf::from("./outboard.temp"))?;
+        assert_eq!(
+            reader.read_range("DATA", 16_000..40_000)?,
+            &data[16_000..40_000]
+        );
+        assert_eq!(reader.open_nested("NESTED")?.read("INNER")?, data);
+        assert_eq!(reader.read_range("SMALL", 1..3)?, b"ma");
+        assert!(matches!(
+            reader.read_range("SMALL", 1..6).unwrap_err(),
+            SnowBinError::RangeOutOfBounds { size: 5 }
+        ));
+        let mut streamed = Vec::new();
+        reader.stream("DATA")?.read_to_end(&mut streamed).unwrap();
+        assert_eq!(streamed, data);
+
+        // Corrupt a byte in the second group of "DATA".
+        let mut bytes = std::fs::read("./outboard.temp").unwrap();
+        let offset = bytes.windows(4).position(|w| w == &data[..4]).unwrap() + 20_000;
+        bytes[offset] ^= 1;
+        std::fs::write("./outboard.temp", &bytes).unwrap();
+
+        let path = PathBuf::from("./outboard.temp");
+        let mut reader = SnowBinReader::unverified(path, SnowBinLimits::default())?;
+        assert_eq!(reader.read_range("DATA", 0..16_000)?, &data[..16_000]);
+        assert_eq!(
+            reader.read_range("DATA", 40_000..50_000)?,
+            &data[40_000..50_000]
+        );
+        assert!(matches!(
+            reader.read_range("DATA", 10_000..20_000).unwrap_err(),
+            SnowBinError::HashDoesNotMatch
+        ));
+        assert!(matches!(
+            reader.read("DATA").unwrap_err(),
+            SnowBinError::HashDoesNotMatch
+        ));
+        let mut stream = reader.stream("DATA")?;
+        let mut buffer = vec![0_u8; 16 * 1024];
+        stream.read_exact(&mut buffer).unwrap();
+        assert_eq!(
+            stream.read_exact(&mut buffer).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(reader.read("SMALL")?, b"small");
+        let mut subtree = reader.subtree("DATA")?;
+        assert!(matches!(
+            subtree.next().unwrap().unwrap_err(),
+            SnowBinError::HashDoesNotMatch
+        ));
+
+        assert!(matches!(
+            SnowBinReader::new(PathBuf::from("./outboard.temp")).unwrap_err(),
+            SnowBinError::HashDoesNotMatch
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn large_payload_test() -> Result<(), SnowBinError> {
+        // Large enough to be hashed on several threads with the `parallel` feature.
+        let data = (0..3_000_000_u32)
+            .map(|i| (i % 241) as u8)
+            .collect::<Vec<_>>();
+        {
+            let mut writer =
+                SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("./large.temp"))?;
+            writer.write("LARGE", &data)?;
+            {
+                let mut nested = writer.write_nested("NESTED", SnowBinInfo::default())?;
+                nested.write("LARGE", &data)?;
+            }
+        }
+
+        let bytes = std::fs::read("./large.temp").unwrap();
+        let (body, hash) = bytes.split_at(bytes.len() - 32);
+        assert_eq!(blake3::hash(body).as_bytes(), hash);
+
+        let mut reader = SnowBinReader::new(PathBuf::from("./large.temp"))?;
+        assert_eq!(reader.read("LARGE")?, data);
+        assert_eq!(reader.open_nested("NESTED")?.read("LARGE")?, data);
+        assert_eq!(SnowBinDecoder::new(&bytes)?.read("LARGE")?, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn shared_reader_test() -> Result<(), SnowBinError> {
+        fn assert_sync<T: Send + Sync>() {}
+        assert_sync::<SnowBinSharedReader>();
+
+        let info = SnowBinInfo::new(16, 32)?.with_outboard();
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./shared.temp"))?;
+            for i in 0..32_u8 {
+                writer.write(&format!("ENTRY_{i}"), &vec![i; 1000 * usize::from(i)])?;
+            }
+            {
+                let mut nested = writer.write_nested("NESTED", SnowBinInfo::default())?;
+                nested.write("INNER", b"inner")?;
+            }
+        }
+
+        let reader = Arc::new(SnowBinSharedReader::new(PathBuf::from("./shared.temp"))?);
+        std::thread::scope(|scope| {
+            for worker in 0..8_u8 {
+                let reader = Arc::clone(&reader);
+                scope.spawn(move || {
+                    for i in (worker..32).step_by(8) {
+                        let data = reader.read(&format!("ENTRY_{i}")).unwrap();
+                        assert_eq!(data, vec![i; 1000 * usize::from(i)]);
+                    }
+                    assert_eq!(
+                        reader.open_nested("NESTED").unwrap().read("INNER").unwrap(),
+                        b"inner"
+                    );
+                });
+            }
+        });
+
+        assert_eq!(reader.headers()?.len(), 33);
+        assert_eq!(reader.read_range("ENTRY_31", 30_990..31_000)?, [31; 10]);
+        assert!(matches!(
+            reader.read("MISSING").unwrap_err(),
+            SnowBinError::ReachedEOF
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn finish_test() -> Result<(), SnowBinError> {
+        let mut writer =
+            SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("./finish.temp"))?;
+        writer.write("FIRST", b"first")?;
+        {
+            let mut nested = writer.write_nested("NESTED", SnowBinInfo::default())?;
+            nested.write("INNER", b"inner")?;
+        }
+        writer.write("LAST", b"last")?;
+        let summary = writer.finish()?;
+
+        let bytes = std::fs::read("./finish.temp").unwrap();
+        assert_eq!(summary.entries(), 3);
+        assert_eq!(summary.size(), bytes.len() as u64);
+        assert_eq!(summary.hash(), bytes[bytes.len() - 32..]);
+
+        let info = SnowBinInfo::default();
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./abort.temp"))?
+                .on_drop(SnowBinDropAction::Abort);
+            writer.write("DATA", b"data")?;
+        }
+        assert!(SnowBinReader::new(PathBuf::from("./abort.temp")).is_err());
+
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./remove.temp"))?
+                .on_drop(SnowBinDropAction::Remove);
+            writer.write("DATA", b"data")?;
+        }
+        assert!(!PathBuf::from("./remove.temp").exists());
+
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./close.temp"))?;
+            writer.write("DATA", b"data")?;
+        }
+        assert_eq!(
+            SnowBinReader::new(PathBuf::from("./close.temp"))?.read("DATA")?,
+            b"data"
+        );
+
+        // Atomic writers to the same path do not share a temporary file.
+        let options = SnowBinOpenOptions::new().atomic(true);
+        let mut first = options.create(info, PathBuf::from("./shared_temp.temp"))?;
+        let mut second = options.create(info, PathBuf::from("./shared_temp.temp"))?;
+        first.write("FIRST", b"first")?;
+        second.write("SECOND", b"second")?;
+        first.close()?;
+        second.close()?;
+        assert_eq!(
+            SnowBinReader::new(PathBuf::from("./shared_temp.temp"))?.read("SECOND")?,
+            b"second"
+        );
+
+        // A write that failed is not counted, and a close that failed partway is not run again.
+        #[cfg(target_os = "linux")]
+        {
+            let mut writer = SnowBinOpenOptions::new()
+                .buffer_size(4096)
+                .duplicates(SnowBinDuplicates::Reject)
+                .create(info, PathBuf::from("/dev/full"))?;
+            writer.write("DATA", b"data")?;
+            for _ in 0..2 {
+                assert!(matches!(
+                    writer.write("BIG", &[0_u8; 8192]),
+                    Err(SnowBinError::IOWriteError { .. })
+                ));
+            }
+            assert!(matches!(
+                writer.close(),
+                Err(SnowBinError::IOWriteError { .. })
+            ));
+            assert!(matches!(writer.close(), Err(SnowBinError::IOWriterClosed)));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn builder_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::builder()
+            .header_size(32)
+            .data_size(16)
+            .varint_sizes()
+            .alignment(8)
+            .separator('/')
+            .build()?;
+        assert_eq!(info.header_size(), 32);
+        assert_eq!(info.data_size(), 16);
+        assert!(info.varint_sizes());
+        assert_eq!(info.alignment(), 8);
+        assert_eq!(info.separator(), Some('/'));
+        assert_eq!(SnowBinInfo::builder().build()?, SnowBinInfo::default());
+        assert!(matches!(
+            SnowBinInfo::builder().header_size(4).build(),
+            Err(SnowBinError::HeaderSizeTooSmall)
+        ));
+        assert!(matches!(
+            SnowBinInfo::builder().alignment(3).build(),
+            Err(SnowBinError::AlignmentNotAllowed)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn options_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::new(32, 16)?.with_separator('/')?;
+
+        // Duplicates are rejected by the writer and by readers.
+        let options = SnowBinOpenOptions::new().duplicates(SnowBinDuplicates::Reject);
+        {
+            let mut writer = options.create(info, PathBuf::from("./duplicates.temp"))?;
+            writer.write("a/b", b"one")?;
+            assert!(matches!(
+                writer.write("a/b", b"two"),
+                Err(SnowBinError::DuplicateHeader)
+            ));
+            writer.close()?;
+        }
+        assert_eq!(
+            options
+                .open(PathBuf::from("./duplicates.temp"))?
+                .read("a/b")?,
+            b"one"
+        );
+
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./duplicates.temp"))?;
+            writer.write("a/b", b"one")?;
+            writer.write("a/b", b"two")?;
+        }
+        assert_eq!(
+            SnowBinReader::new(PathBuf::from("./duplicates.temp"))?.read("a/b")?,
+            b"one"
+        );
+        assert!(matches!(
+            options.open(PathBuf::from("./duplicates.temp")),
+            Err(SnowBinError::DuplicateHeader)
+        ));
+        assert!(matches!(
+            options.open_shared(PathBuf::from("./duplicates.temp")),
+            Err(SnowBinError::DuplicateHeader)
+        ));
+
+        // Atomic writes only show up once closed.
+        let _ = std::fs::remove_file("./atomic.temp");
+        let options = SnowBinOpenOptions::new().atomic(true).buffer_size(0);
+        let mut writer = options.create(info, PathBuf::from("./atomic.temp"))?;
+        writer.write("DATA", &[7_u8; 1000])?;
+        assert!(!PathBuf::from("./atomic.temp").exists());
+        writer.close()?;
+        assert_eq!(
+            options
+                .verification(SnowBinVerification::Skip)
+                .open(PathBuf::from("./atomic.temp"))?
+                .read("DATA")?,
+            [7_u8; 1000]
+        );
+
+        {
+            let mut writer = options
+                .on_drop(SnowBinDropAction::Remove)
+                .create(info, PathBuf::from("./atomic_remove.temp"))?;
+            writer.write("DATA", b"data")?;
+        }
+        assert!(!PathBuf::from("./atomic_remove.temp").exists());
+        let leftovers = std::fs::read_dir(".")
+            .map_err(|source| SnowBinError::IOReadError {
+                offset: 0,
+                header: None,
+                source,
+            })?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(".atomic"))
+            .count();
+        assert_eq!(leftovers, 0);
+
+        // Small buffers and limits still read everything.
+        let options = SnowBinOpenOptions::new()
+            .buffer_size(3)
+            .limits(SnowBinLimits::new().max_entry_size(16));
+        {
+            let mut writer = options.create(info, PathBuf::from("./buffered.temp"))?;
+            for i in 0..100_u8 {
+                writer.write(&format!("entries/{i}"), &[i; 5])?;
+            }
+            writer.write("big", &[0_u8; 17])?;
+        }
+        // Aborted writers still write out what they buffered.
+        {
+            let mut writer = options
+                .buffer_size(4096)
+                .on_drop(SnowBinDropAction::Abort)
+                .create(SnowBinInfo::default(), PathBuf::from("./tail.temp"))?;
+            writer.write("tail", b"buffered tail")?;
+        }
+        assert!(std::fs::read("./tail.temp")
+            .unwrap()
+            .ends_with(b"buffered tail"));
+        let mut reader = options.open(PathBuf::from("./buffered.temp"))?;
+        assert_eq!(reader.read("entries/42")?, [42_u8; 5]);
+        assert_eq!(reader.read("entries/99")?, [99_u8; 5]);
+        assert!(matches!(
+            reader.read("big"),
+            Err(SnowBinError::LimitExceeded {
+                limit: SnowBinLimit::EntrySize,
+                ..
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn plan_test() -> Result<(), SnowBinError> {
+        assert_eq!(SnowBinPlan::new().info()?, SnowBinInfo::new(8, 8)?);
+
+        let mut plan = SnowBinPlan::new();
+        plan.add("DATA", 255);
+        assert_eq!(plan.info()?.data_size(), 8);
+        plan.add("DATA", 256);
+        assert_eq!(plan.info()?.data_size(), 16);
+        plan.add("DATA", u64::from(u32::MAX) + 1);
+        assert_eq!(plan.info()?.data_size(), 64);
+        plan.add("A header of thirty two bytes!!!!", 0);
+        assert_eq!(plan.info()?.header_size(), 32);
+
+        let options = SnowBinInfo::default().with_varint_sizes().with_outboard();
+        let mut plan = SnowBinPlan::with_options(options);
+        plan.add("DATA", u64::MAX);
+        let info = plan.info()?;
+        assert!(info.varint_sizes() && info.outboard());
+        assert_eq!((info.header_size(), info.data_size()), (8, 8));
+
+        let mut staged =
+            SnowBinStagedWriter::with_options(SnowBinInfo::default().with_entry_metadata());
+        let metadata = SnowBinMetadata::new().with_content_type("text/plain");
+        let large = vec![1_u8; 70_000];
+        staged.write("short", b"data");
+        staged.write_with_metadata("a much longer header", &large, &metadata);
+        let info = staged.info()?;
+        assert_eq!((info.header_size(), info.data_size()), (20, 32));
+
+        let bytes = staged.finish()?;
+        let decoder = SnowBinDecoder::new(&bytes)?;
+        assert_eq!(decoder.read("short")?, b"data");
+        assert_eq!(decoder.read("a much longer header")?, large);
+        assert_eq!(decoder.metadata("a much longer header")?, metadata);
+
+        Ok(())
+    }
+
+    #[test]
+    fn probe_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::new(24, 16)?.with_varint_sizes();
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./probe.temp"))?;
+            writer.write("DATA", b"data")?;
+        }
+        let supported = SnowBinProbe::Supported {
+            version: 3,
+            header_size: 24,
+            data_size: 16,
+        };
+        assert_eq!(crate::probe_path(PathBuf::from("./probe.temp"))?, supported);
+
+        let mut bytes =
+            std::fs::read("./probe.temp").map_err(|source| SnowBinError::IOReadError {
+                offset: 0,
+                header: None,
+                source,
+            })?;
+        assert_eq!(crate::probe(&bytes[..25]), supported);
+        assert!(crate::probe(&bytes).is_supported());
+
+        // The hash is not checked.
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert_eq!(crate::probe(&bytes), supported);
+
+        assert_eq!(crate::probe(&bytes[..24]), SnowBinProbe::Truncated);
+        assert_eq!(crate::probe(&bytes[..20]), SnowBinProbe::Truncated);
+        assert_eq!(crate::probe(&bytes[..8]), SnowBinProbe::Truncated);
+        assert_eq!(crate::probe(&bytes[..7]), SnowBinProbe::NotSnowBinary);
+        assert_eq!(crate::probe(b""), SnowBinProbe::NotSnowBinary);
+        assert!(!crate::probe(b"SNOW_BIX").is_snowbinary());
+
+        // Flags and data sizes this library cannot read are caught, in the file too.
+        let mut unsupported = bytes.clone();
+        unsupported[21..25].copy_from_slice(&(1_u32 << 31).to_le_bytes());
+        assert_eq!(
+            crate::probe(&unsupported),
+            SnowBinProbe::UnsupportedFlags { flags: 1 << 31 }
+        );
+        std::fs::write("./probe_flags.temp", &unsupported).unwrap();
+        assert_eq!(
+            crate::probe_path(PathBuf::from("./probe_flags.temp"))?,
+            SnowBinProbe::UnsupportedFlags { flags: 1 << 31 }
+        );
+        // Known flags that are not valid are malformed instead.
+        unsupported[21..25].copy_from_slice(&0_u32.to_le_bytes());
+        assert_eq!(
+            crate::probe(&unsupported),
+            SnowBinProbe::MalformedFlags { flags: 0 }
+        );
+        unsupported[21..25].copy_from_slice(&(17_u32 << 8).to_le_bytes());
+        assert_eq!(
+            crate::probe(&unsupported),
+            SnowBinProbe::MalformedFlags { flags: 17 << 8 }
+        );
+        unsupported[21..25].copy_from_slice(&(0xe9_u32 << 16).to_le_bytes());
+        assert_eq!(
+            crate::probe(&unsupported),
+            SnowBinProbe::MalformedFlags { flags: 0xe9 << 16 }
+        );
+        unsupported[20] = 7;
+        let probe = crate::probe(&unsupported);
+        assert_eq!(probe, SnowBinProbe::DataSizeNotAllowed { data_size: 7 });
+        assert!(probe.is_snowbinary() && !probe.is_supported());
+
+        bytes[8..16].copy_from_slice(&4_u64.to_le_bytes());
+        let probe = crate::probe(&bytes);
+        assert_eq!(probe, SnowBinProbe::UnsupportedVersion { version: 4 });
+        assert!(probe.is_snowbinary() && !probe.is_supported());
+
+        assert!(matches!(
+            crate::probe_path(PathBuf::from("./missing_probe.temp")),
+            Err(SnowBinError::CouldNotCreateOrOpenFile { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn salvage_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::default().with_outboard().with_entry_metadata();
+        let metadata = SnowBinMetadata::new().with_content_type("text/plain");
+        let mut encoder = SnowBinEncoder::new(info)?;
+        encoder.write("ONE", b"one")?;
+        encoder.write_with_metadata("TWO", &vec![2_u8; 40_000], &metadata)?;
+        encoder.write("THREE", b"three")?;
+        let bytes = encoder.finish()?;
+
+        let salvage = SnowBinSalvage::new(&bytes)?;
+        assert!(salvage.is_complete());
+        assert_eq!(salvage.entries().len(), 3);
+        assert_eq!(salvage.entries()[1].metadata(), &metadata);
+        assert_eq!(salvage.encode()?, bytes);
+
+        // A bad hash only loses the hash.
+        let mut corrupted = bytes.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        let salvage = SnowBinSalvage::new(&corrupted)?;
+        assert!(salvage.error().is_none() && !salvage.hash_matches());
+        assert_eq!(salvage.entries().len(), 3);
+        assert_eq!(
+            SnowBinDecoder::new(&salvage.encode()?)?.read("THREE")?,
+            b"three"
+        );
+
+        // Corrupted data is caught by the outboard hashes.
+        let mut corrupted = bytes.clone();
+        corrupted[30_000] ^= 1;
+        let salvage = SnowBinSalvage::new(&corrupted)?;
+        assert_eq!(salvage.damaged(), ["TWO"]);
+        let headers = salvage
+            .entries()
+            .iter()
+            .map(crate::SnowBinSalvagedEntry::header)
+            .collect::<Vec<_>>();
+        assert_eq!(headers, ["ONE", "THREE"]);
+        assert!(!salvage.is_complete());
+
+        // Files are walked in place and give the same result.
+        std::fs::write("./salvage.temp", &bytes).unwrap();
+        let salvage = SnowBinSalvage::from_path(PathBuf::from("./salvage.temp"))?;
+        assert!(salvage.is_complete());
+        assert_eq!(salvage.encode()?, bytes);
+        std::fs::write("./salvage.temp", &corrupted).unwrap();
+        let salvage = SnowBinSalvage::from_path(PathBuf::from("./salvage.temp"))?;
+        assert_eq!(salvage.damaged(), ["TWO"]);
+        assert!(salvage.error().is_none() && !salvage.hash_matches());
+
+        // Truncated files keep every entry before the cut.
+        let truncated = &bytes[..30_000];
+        let salvage = SnowBinSalvage::new(truncated)?;
+        assert_eq!(salvage.entries().len(), 1);
+        assert_eq!(salvage.entries()[0].data(), b"one");
+        assert!(matches!(
+            salvage.error(),
+            Some(SnowBinError::UnexpectedEof { .. })
+        ));
+        assert!(salvage.stopped_at() > 25 && salvage.stopped_at() < 100);
+        let recovered = salvage.encode()?;
+        let recovered = SnowBinDecoder::new(&recovered)?;
+        assert_eq!(recovered.read("ONE")?, b"one");
+        assert!(matches!(
+            recovered.read("TWO"),
+            Err(SnowBinError::ReachedEOF)
+        ));
+
+        let salvage = SnowBinSalvage::new(&bytes[..25])?;
+        assert!(salvage.entries().is_empty() && salvage.error().is_some());
+        assert!(matches!(
+            SnowBinSalvage::new(b"SNOW_BIN"),
+            Err(SnowBinError::UnexpectedEof { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_test() -> Result<(), SnowBinError> {
+        let texture = vec![7_u8; 40_000];
+        let info = SnowBinInfo::builder()
+            .header_size(16)
+            .dedup()
+            .outboard()
+            .alignment(64)
+            .build()?;
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./dedup.temp"))?;
+            writer.write("ui/button", &texture)?;
+            writer.write("config", b"defaults")?;
+            writer.write("ui/slider", &texture)?;
+            writer.write("config.bak", b"defaults")?;
+            writer.write("empty", b"")?;
+            writer.write("empty.bak", b"")?;
+            let summary = writer.finish()?;
+            assert_eq!(summary.entries, 6);
+            assert!(summary.size < texture.len() as u64 + 1_000);
+        }
+
+        let mut reader = SnowBinReader::new(PathBuf::from("./dedup.temp"))?;
+        assert_eq!(reader.read("ui/slider")?, texture);
+        assert_eq!(reader.read("config.bak")?, b"defaults");
+        assert_eq!(reader.read("empty.bak")?, b"");
+        assert_eq!(reader.read_range("ui/slider", 39_990..40_000)?, [7_u8; 10]);
+        let mut streamed = Vec::new();
+        reader
+            .stream("ui/slider")?
+            .read_to_end(&mut streamed)
+            .unwrap();
+        assert_eq!(streamed, texture);
+        assert_eq!(reader.headers()?.len(), 6);
+
+        let diff = reader.diff(&mut SnowBinReader::new(PathBuf::from("./dedup.temp"))?)?;
+        assert!(!diff.has_changes());
+        let salvage = SnowBinSalvage::from_path(PathBuf::from("./dedup.temp"))?;
+        assert!(salvage.is_complete());
+        assert_eq!(salvage.entries()[2].data(), texture);
+
+        // Streamed entries are stored, and later copies of them refer to them.
+        let child_info = SnowBinInfo::default().with_dedup();
+        let mut child = SnowBinEncoder::new(child_info)?;
+        child.write("A", b"shared")?;
+        child.write("B", b"shared")?;
+        let child = child.finish()?;
+
+        let mut encoder = SnowBinEncoder::new(SnowBinInfo::default().with_dedup())?;
+        {
+            let mut bundle = encoder.write_nested("BUNDLE", child_info)?;
+            bundle.write("A", b"shared")?;
+            bundle.write("B", b"shared")?;
+        }
+        encoder.write("COPY", &child)?;
+        let bytes = encoder.finish()?;
+        assert!(bytes.len() < 2 * child.len());
+
+        let decoder = SnowBinDecoder::new(&bytes)?;
+        assert_eq!(decoder.read("COPY")?, child);
+        assert_eq!(decoder.open_nested("COPY")?.read("B")?, b"shared");
+
+        // A reference must point before its own entry.
+        let mut encoder = SnowBinEncoder::new(SnowBinInfo::default().with_dedup())?;
+        encoder.write("A", b"data")?;
+        encoder.write("B", b"data")?;
+        let mut bytes = encoder.finish()?;
+        // File info (25) + entry A (8 + 8 + 1 + 4) + header and length of B (8 + 8).
+        assert_eq!(bytes[62], 42);
+        bytes[62] = 100;
+        let salvage = SnowBinSalvage::new(&bytes)?;
+        assert!(matches!(
+            salvage.error(),
+            Some(SnowBinError::InvalidReference { offset: 62 })
+        ));
+        // It must also point at the start of a payload, not anywhere before its entry.
+        bytes[62] = 25;
+        let salvage = SnowBinSalvage::new(&bytes)?;
+        assert!(matches!(
+            salvage.error(),
+            Some(SnowBinError::InvalidReference { offset: 62 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn schema_test() -> Result<(), SnowBinError> {
+        let schema = SnowBinSchema::new()
+            .required("name", SnowBinField::new(SnowBinValueType::Utf8))
+            .required("scale", SnowBinField::new(SnowBinValueType::F64))
+            .required("bundle", SnowBinField::new(SnowBinValueType::SnowBinary))
+            .required("icon", SnowBinField::default().min_size(4).max_size(16))
+            .optional("notes", SnowBinField::new(SnowBinValueType::Utf8))
+            .required("version", SnowBinField::new(SnowBinValueType::U32));
+
+        let bundle = SnowBinEncoder::new(SnowBinInfo::default())?.finish()?;
+        {
+            let mut writer =
+                SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("./schema.temp"))?;
+            writer.write("name", &[0xff, 0xfe])?;
+            writer.write("scale", &1.5_f64.to_le_bytes())?;
+            writer.write("bundle", b"SNOW_BIN")?;
+            writer.write("icon", &[0_u8; 20])?;
+            writer.write("name", b"ignored duplicate")?;
+            writer.write("extra", b"allowed")?;
         }
 
+        let mut reader = SnowBinReader::new(PathBuf::from("./schema.temp"))?;
+        let violations = reader.validate(&schema)?;
+        assert_eq!(
+            violations,
+            [
+                SnowBinViolation::WrongType {
+                    header: String::from("name"),
+                    expected: SnowBinValueType::Utf8
+                },
+                SnowBinViolation::WrongType {
+                    header: String::from("bundle"),
+                    expected: SnowBinValueType::SnowBinary
+                },
+                SnowBinViolation::SizeOutOfRange {
+                    header: String::from("icon"),
+                    size: 20,
+                    min: 4,
+                    max: 16
+                },
+                SnowBinViolation::Missing {
+                    header: String::from("version")
+                },
+            ]
+        );
+        assert_eq!(violations[1].header(), "bundle");
+        assert_eq!(
+            violations[2].to_string(),
+            "Header \"icon\" has 20 bytes of data, expected 4 to 16."
+        );
+
+        // The same entries are caught while writing.
+        assert_eq!(schema.validate_entry("extra", b"?"), []);
+        assert_eq!(
+            schema.clone().deny_unknown().validate_entry("extra", b"?"),
+            [SnowBinViolation::Unexpected {
+                header: String::from("extra")
+            }]
+        );
+        assert_eq!(schema.validate_entry("version", &[0_u8; 4]), []);
+        assert_eq!(schema.validate_entry("bundle", &bundle), []);
+
+        let mut staged = SnowBinStagedWriter::new();
+        staged.write("name", b"Snow");
+        staged.write("scale", &2.0_f64.to_le_bytes());
+        staged.write("bundle", &bundle);
+        staged.write("icon", &[1_u8; 8]);
+        assert_eq!(
+            staged.validate(&schema),
+            [SnowBinViolation::Missing {
+                header: String::from("version")
+            }]
+        );
+        staged.write("version", &3_u32.to_le_bytes());
+        assert_eq!(staged.validate(&schema), []);
+        let bytes = staged.finish()?;
+        assert_eq!(SnowBinDecoder::new(&bytes)?.validate(&schema)?, []);
+
         Ok(())
     }
+
+    #[test]
+    fn map_test() -> Result<(), SnowBinError> {
+        let info = SnowBinInfo::new(16, 32)?.with_entry_metadata();
+        let metadata = SnowBinMetadata::new().with_content_type("text/plain");
+        {
+            let mut writer = SnowBinWriter::new(info, PathBuf::from("./map.temp"))?;
+            writer.write_with_metadata("readme", b"first", &metadata)?;
+            writer.write("config", b"old")?;
+            writer.write("readme", b"ignored duplicate")?;
+        }
+
+        let mut map = SnowBinMap::open(PathBuf::from("./map.temp"))?;
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("readme")?.as_deref(), Some(&b"first"[..]));
+        assert_eq!(map.get("missing")?, None);
+        assert!(map.flush()?.is_none());
+
+        map.insert("config", b"new")?;
+        map.insert("added", b"added")?;
+        assert!(map.remove("readme")? && !map.remove("readme")?);
+        assert!(map.is_dirty());
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["added", "config"]);
+        assert_eq!(map.get("config")?.as_deref(), Some(&b"new"[..]));
+        assert!(matches!(
+            map.insert("a key longer than 16", b""),
+            Err(SnowBinError::HeaderTooLong)
+        ));
+
+        // Nothing is written until the map is flushed.
+        let mut reader = SnowBinReader::new(PathBuf::from("./map.temp"))?;
+        assert_eq!(reader.read("config")?, b"old");
+        drop(map);
+        let mut map = SnowBinMap::open(PathBuf::from("./map.temp"))?;
+        assert!(map.contains_key("readme") && !map.contains_key("added"));
+
+        map.insert("config", b"new")?;
+        let summary = map.flush()?.unwrap();
+        assert_eq!(summary.entries, 2);
+        assert!(!map.is_dirty());
+        assert_eq!(map.info(), info);
+        assert_eq!(map.get("config")?.as_deref(), Some(&b"new"[..]));
+
+        // The rewritten file keeps metadata, drops duplicates, and passes verification.
+        let mut reader = SnowBinReader::new(PathBuf::from("./map.temp"))?;
+        assert_eq!(reader.headers()?, ["config", "readme"]);
+        assert_eq!(reader.metadata("readme")?, metadata);
+        assert_eq!(reader.read("readme")?, b"first");
+
+        let options = SnowBinOpenOptions::new().verification(SnowBinVerification::Skip);
+        let mut map = options.create_map(SnowBinInfo::default(), PathBuf::from("./map.temp"))?;
+        assert!(map.is_empty());
+        map.insert("Header", b"data")?;
+        map.flush()?;
+        let mut map = options.open_map(PathBuf::from("./map.temp"))?;
+        assert_eq!(map.get("Header")?.as_deref(), Some(&b"data"[..]));
+
+        // A map that could not be reopened after its file was replaced is reopened later.
+        let mut map = SnowBinMap::create(SnowBinInfo::default(), PathBuf::from("./stale.temp"))?;
+        map.insert("Header", b"data")?;
+        std::fs::create_dir_all("./stale.temp.journal").unwrap();
+        assert!(map.flush().is_err());
+        assert!(!map.is_dirty());
+        assert!(map.get("Header").is_err());
+        std::fs::remove_dir("./stale.temp.journal").unwrap();
+        assert_eq!(map.get("Header")?.as_deref(), Some(&b"data"[..]));
+        assert!(map.flush()?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn journal_test() -> Result<(), SnowBinError> {
+        let path = PathBuf::from("./journal.temp");
+        let journal = PathBuf::from("./journal.temp.journal");
+        let options = SnowBinOpenOptions::new().journal(true);
+
+        // Changes made before a crash are replayed when the map is opened again.
+        {
+            let mut map = options.create_map(SnowBinInfo::default(), path.clone())?;
+            map.insert("A", b"a")?;
+            map.flush()?;
+            map.insert("B", b"b")?;
+            map.insert("A", b"changed")?;
+            assert!(map.remove("B")?);
+            map.insert("C", b"c")?;
+        }
+        assert!(journal.exists());
+        let mut reader = SnowBinReader::new(path.clone())?;
+        assert_eq!(reader.read("A")?, b"a");
+
+        // They are kept in the journal until the map is flushed.
+        let mut map = SnowBinMap::open(path.clone())?;
+        assert!(journal.exists() && map.is_dirty());
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["A", "C"]);
+        assert_eq!(map.get("A")?.as_deref(), Some(&b"changed"[..]));
+        map.flush()?;
+        assert!(!journal.exists());
+        let mut reader = SnowBinReader::new(path.clone())?;
+        assert_eq!(reader.read("C")?, b"c");
+
+        // A partially written change is discarded, along with anything after it.
+        {
+            let mut map = options.open_map(path.clone())?;
+            map.insert("D", b"d")?;
+            map.insert("E", b"e")?;
+        }
+        let len = std::fs::metadata(&journal).unwrap().len();
+        File::options()
+            .write(true)
+            .open(&journal)
+            .unwrap()
+            .set_len(len - 3)
+            .unwrap();
+        let mut map = options.open_map(path.clone())?;
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["A", "C", "D"]);
+
+        // So is a corrupted change.
+        map.insert("F", b"f")?;
+        map.insert("G", b"g")?;
+        drop(map);
+        let mut bytes = std::fs::read(&journal).unwrap();
+        let at = bytes.len() - 40;
+        bytes[at] ^= 1;
+        std::fs::write(&journal, bytes).unwrap();
+        let mut map = options.open_map(path.clone())?;
+        assert!(map.contains_key("F") && !map.contains_key("G"));
+        // Changes made after a replay are appended to the replayed ones.
+        assert!(map.contains_key("D"));
+
+        // A journal that was applied, but not deleted before a crash, is not applied again.
+        map.insert("H", b"h")?;
+        map.remove("A")?;
+        let stale = std::fs::read(&journal).unwrap();
+        map.insert("A", b"back")?;
+        map.flush()?;
+        std::fs::write(&journal, stale).unwrap();
+        let mut map = SnowBinMap::open(path.clone())?;
+        assert!(!journal.exists());
+        assert_eq!(map.get("A")?.as_deref(), Some(&b"back"[..]));
+        assert!(map.contains_key("H"));
+
+        // Creating a map discards the journal of the old file.
+        {
+            let mut map = options.open_map(path.clone())?;
+            map.insert("I", b"i")?;
+        }
+        let map = options.create_map(SnowBinInfo::default(), path)?;
+        assert!(map.is_empty() && !journal.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn diff_test() -> Result<(), SnowBinError> {
+        let large = vec![3_u8; 100_000];
+        {
+            let mut old = SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("./old.temp"))?;
+            old.write("SAME", &large)?;
+            old.write("GONE", b"gone")?;
+            old.write("EDIT", b"before")?;
+            old.write("EDIT", b"ignored duplicate")?;
+
+            // Outboard hashes are used instead of hashing, and must agree with hashing.
+            let info = SnowBinInfo::new(16, 32)?.with_outboard();
+            let mut new = SnowBinWriter::new(info, PathBuf::from("./new.temp"))?;
+            new.write("EDIT", b"after!!")?;
+            new.write("NEW", b"new")?;
+            new.write("SAME", &large)?;
+        }
+
+        let mut old = SnowBinReader::new(PathBuf::from("./old.temp"))?;
+        let mut new = SnowBinReader::new(PathBuf::from("./new.temp"))?;
+        let diff = old.diff(&mut new)?;
+        assert!(diff.has_changes());
+
+        let entries = diff
+            .entries()
+            .iter()
+            .map(|entry| (entry.header(), entry.change(), entry.size_delta()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                ("SAME", SnowBinChange::Unchanged, 0),
+                ("GONE", SnowBinChange::Removed, -4),
+                ("EDIT", SnowBinChange::Changed, 1),
+                ("NEW", SnowBinChange::Added, 3),
+            ]
+        );
+        let edit = &diff.entries()[2];
+        assert_eq!(edit.old_hash(), Some(*blake3::hash(b"before").as_bytes()));
+        assert_eq!(edit.new_hash(), Some(*blake3::hash(b"after!!").as_bytes()));
+        assert_eq!((edit.old_size(), edit.new_size()), (Some(6), Some(7)));
+        assert_eq!(diff.entries()[1].new_hash(), None);
+        assert_eq!(diff.changes().count(), 3);
+
+        let diff = new.diff(&mut new_reader("./new.temp")?)?;
+        assert!(!diff.has_changes());
+        assert_eq!(diff.entries().len(), 3);
+
+        assert_eq!(SnowBinDiff::bytes(b"same", b"same"), []);
+        assert_eq!(SnowBinDiff::bytes(b"xabcd", b"yab"), [0..1, 3..5]);
+        assert_eq!(SnowBinDiff::bytes(b"", b"ab").first(), Some(&(0..2)));
+
+        assert_eq!(SnowBinDiff::lines("", ""), []);
+        assert_eq!(
+            SnowBinDiff::lines("a\nb", ""),
+            [SnowBinDiffLine::Removed("a"), SnowBinDiffLine::Removed("b")]
+        );
+        assert_eq!(SnowBinDiff::lines("", "a"), [SnowBinDiffLine::Added("a")]);
+        let lines = SnowBinDiff::lines("a\nb\nc\na\nb\nb\na", "c\nb\na\nb\na\nc");
+        let unchanged = lines
+            .iter()
+            .filter(|line| matches!(line, SnowBinDiffLine::Unchanged(_)))
+            .count();
+        assert_eq!((lines.len(), unchanged), (9, 4));
+        let rebuilt = lines
+            .iter()
+            .filter_map(|line| match line {
+                SnowBinDiffLine::Unchanged(line) | SnowBinDiffLine::Added(line) => Some(*line),
+                SnowBinDiffLine::Removed(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rebuilt, ["c", "b", "a", "b", "a", "c"]);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn tar_test() -> Result<(), SnowBinError> {
+        let mut archive = tar::Builder::new(Vec::new());
+        let mut add = |path: &str, kind: tar::EntryType, data: &[u8]| {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(kind);
+            header.set_size(data.len() as u64);
+            header.set_mtime(1_700_000_000);
+            archive.append_data(&mut header, path, data).unwrap();
+        };
+        add("docs/", tar::EntryType::Directory, b"");
+        add(
+            "./docs/readme.txt",
+            tar::EntryType::Regular,
+            b"This is data!",
+        );
+        add("logo", tar::EntryType::Regular, b"logo");
+        add("link", tar::EntryType::Symlink, b"");
+        add("a/path/too/long/for/it", tar::EntryType::Regular, b"data");
+        let archive = archive.into_inner().unwrap();
+
+        let info = SnowBinInfo::new(16, 64)?.with_entry_metadata();
+        let mut writer = SnowBinWriter::new(info, PathBuf::from("./tar.temp"))?;
+        let report = writer.import_tar(&archive[..])?;
+        writer.write("bad/../header", b"data")?;
+        writer.write("logo", b"second logo")?;
+        writer.close()?;
+        assert_eq!(report.entries(), 2);
+        assert_eq!(report.skipped(), ["link", "a/path/too/long/for/it"]);
+
+        let mut reader = new_reader("./tar.temp")?;
+        assert_eq!(reader.read("docs/readme.txt")?, b"This is data!");
+        assert_eq!(
+            reader.metadata("docs/readme.txt")?.modified(),
+            Some(1_700_000_000)
+        );
+
+        let mut exported = Vec::new();
+        let report = reader.export_tar(&mut exported)?;
+        assert_eq!(report.entries(), 2);
+        assert_eq!(report.skipped(), ["bad/../header", "logo"]);
+
+        let mut archive = tar::Archive::new(&exported[..]);
+        let mut entries = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut data = String::new();
+            entry.read_to_string(&mut data).unwrap();
+            let path = entry.path().unwrap().to_string_lossy().into_owned();
+            entries.push((path, data, entry.header().mtime().unwrap()));
+        }
+        assert_eq!(
+            entries,
+            [
+                (
+                    String::from("docs/readme.txt"),
+                    String::from("This is data!"),
+                    1_700_000_000
+                ),
+                (String::from("logo"), String::from("logo"), 1_700_000_000),
+            ]
+        );
+
+        let info = SnowBinInfo::new(16, 64)?.with_separator('.')?;
+        let mut writer = SnowBinWriter::new(info, PathBuf::from("./tar.temp"))?;
+        let report = writer.import_tar(&exported[..])?;
+        writer.close()?;
+        assert_eq!(report.skipped(), ["docs/readme.txt"]);
+        assert_eq!(new_reader("./tar.temp")?.read("logo")?, b"logo");
+
+        assert!(matches!(
+            SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("./tar.temp"))?
+                .import_tar(&b"not a tar archive"[..]),
+            Err(SnowBinError::ArchiveError { header: None, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zip_test() -> Result<(), SnowBinError> {
+        use std::io::{Cursor, Write};
+
+        let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().last_modified_time(
+            zip::DateTime::from_date_and_time(2023, 11, 14, 22, 13, 20).unwrap(),
+        );
+        archive.add_directory("docs/", options).unwrap();
+        archive.start_file("docs/readme.txt", options).unwrap();
+        archive.write_all(b"This is data!").unwrap();
+        archive
+            .add_symlink("link", "docs/readme.txt", options)
+            .unwrap();
+        archive
+            .start_file("a/path/too/long/for/it", options)
+            .unwrap();
+        archive.write_all(b"data").unwrap();
+        let archive = archive.finish().unwrap();
+
+        let info = SnowBinInfo::new(16, 64)?.with_entry_metadata();
+        let mut writer = SnowBinWriter::new(info, PathBuf::from("./zip.temp"))?;
+        let report = writer.import_zip(archive)?;
+        writer.write("/absolute", b"data")?;
+        writer.close()?;
+        assert_eq!(report.entries(), 1);
+        assert_eq!(report.skipped(), ["link", "a/path/too/long/for/it"]);
+
+        let mut reader = new_reader("./zip.temp")?;
+        assert_eq!(
+            reader.metadata("docs/readme.txt")?.modified(),
+            Some(1_700_000_000)
+        );
+
+        let mut exported = Cursor::new(Vec::new());
+        let report = reader.export_zip(&mut exported)?;
+        assert_eq!(report.entries(), 1);
+        assert_eq!(report.skipped(), ["/absolute"]);
+
+        let mut archive = zip::ZipArchive::new(exported).unwrap();
+        assert_eq!(archive.len(), 1);
+        let mut file = archive.by_index(0).unwrap();
+        assert_eq!(file.name().unwrap(), "docs/readme.txt");
+        assert_eq!(file.compression(), zip::CompressionMethod::Deflated);
+        let modified = file.last_modified().unwrap();
+        assert_eq!(
+            (modified.year(), modified.month(), modified.day()),
+            (2023, 11, 14)
+        );
+        assert_eq!(
+            (modified.hour(), modified.minute(), modified.second()),
+            (22, 13, 20)
+        );
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"This is data!");
+
+        assert!(matches!(
+            SnowBinWriter::new(SnowBinInfo::default(), PathBuf::from("./zip.temp"))?
+                .import_zip(Cursor::new(b"not a zip archive")),
+            Err(SnowBinError::ArchiveError { header: None, .. })
+        ));
+
+        Ok(())
+    }
+
+    fn new_reader(path: &str) -> Result<SnowBinReader, SnowBinError> {
+        SnowBinReader::new(PathBuf::from(path))
+    }
 }
@@ -1,49 +1,211 @@
-use std::{fs::File, io::Write};
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+};
 
 use crate::error::SnowBinError;
 
-#[inline]
-pub fn error(result: std::io::Result<()>) -> Result<(), SnowBinError> {
-    result.map_or(Err(SnowBinError::IOWriteError), Ok)
+/// A sink of bytes that the format code writes to.
+pub trait Output {
+    /// Writes all of `data`.
+    fn write(&mut self, data: &[u8]) -> Result<(), SnowBinError>;
+
+    /// Flushes any buffered bytes.
+    fn flush(&mut self) -> Result<(), SnowBinError> {
+        Ok(())
+    }
 }
 
-pub fn write_header(
-    file: &mut File,
-    header: &str,
-    header_len: u32,
-) -> Result<Vec<u8>, SnowBinError> {
-    if header.len() > header_len as usize {
-        return Err(SnowBinError::HeaderTooLong);
+/// Output that can rewrite and read back bytes that were already written.
+pub trait Patch: Output {
+    /// Overwrites bytes at `offset`, without moving the position.
+    fn patch(&mut self, offset: u64, data: &[u8]) -> Result<(), SnowBinError>;
+
+    /// Reads bytes at `offset` back into `buffer`, without moving the position.
+    fn read_back(&mut self, offset: u64, buffer: &mut [u8]) -> Result<(), SnowBinError>;
+
+    /// Discards everything from `offset` on, moving the position back to it.
+    fn truncate(&mut self, offset: u64) -> Result<(), SnowBinError>;
+}
+
+impl Output for Vec<u8> {
+    fn write(&mut self, data: &[u8]) -> Result<(), SnowBinError> {
+        self.extend_from_slice(data);
+        Ok(())
     }
+}
 
-    let null_buffer = vec![32_u8; header_len as usize - header.len()];
-    let buffer = [header.as_bytes(), &null_buffer].concat();
+#[allow(clippy::cast_possible_truncation)]
+impl Patch for Vec<u8> {
+    fn patch(&mut self, offset: u64, data: &[u8]) -> Result<(), SnowBinError> {
+        let offset = offset as usize;
+        self[offset..offset + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn read_back(&mut self, offset: u64, buffer: &mut [u8]) -> Result<(), SnowBinError> {
+        let offset = offset as usize;
+        buffer.copy_from_slice(&self[offset..offset + buffer.len()]);
+        Ok(())
+    }
+
+    fn truncate(&mut self, offset: u64) -> Result<(), SnowBinError> {
+        Self::truncate(self, offset as usize);
+        Ok(())
+    }
+}
+
+/// An `Output` over anything that implements `Write`, tracking the current offset.
+/// Writes smaller than `capacity` are buffered, and the buffer is written out when dropped.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct IoOutput<W: Write> {
+    inner: W,
+    offset: u64,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> IoOutput<W> {
+    pub const fn buffered(inner: W, capacity: usize) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            buffer: Vec::new(),
+            capacity,
+        }
+    }
+
+    pub fn error<T>(
+        &mut self,
+        op: impl FnOnce(&mut W) -> std::io::Result<T>,
+    ) -> Result<T, SnowBinError> {
+        self.flush_buffer()?;
+        let offset = self.offset;
+        op(&mut self.inner).map_err(|source| SnowBinError::IOWriteError {
+            offset,
+            header: None,
+            source,
+        })
+    }
 
-    error(file.by_ref().write_all(&buffer))?;
+    /// Writes out the buffered bytes.
+    fn flush_buffer(&mut self) -> Result<(), SnowBinError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
 
-    Ok(buffer)
+        let offset = self.offset - self.buffer.len() as u64;
+        self.inner
+            .write_all(&self.buffer)
+            .map_err(|source| SnowBinError::IOWriteError {
+                offset,
+                header: None,
+                source,
+            })?;
+        self.buffer.clear();
+        Ok(())
+    }
 }
 
-pub fn write_bytes(file: &mut File, data: &[u8]) -> Result<(), SnowBinError> {
-    error(file.by_ref().write_all(data))
+#[cfg(feature = "std")]
+impl<W: Write> Output for IoOutput<W> {
+    fn write(&mut self, data: &[u8]) -> Result<(), SnowBinError> {
+        if self.buffer.len() + data.len() > self.capacity {
+            self.flush_buffer()?;
+        }
+
+        if data.len() >= self.capacity {
+            self.error(|w| w.write_all(data))?;
+        }
+        else {
+            self.buffer.extend_from_slice(data);
+        }
+        self.offset += data.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), SnowBinError> {
+        self.error(Write::flush)
+    }
 }
 
-pub fn write_u8(file: &mut File, data: u8) -> Result<(), SnowBinError> {
-    let buffer = &(data).to_le_bytes();
-    error(file.by_ref().write_all(buffer))
+#[cfg(feature = "std")]
+impl<W: Write> Drop for IoOutput<W> {
+    fn drop(&mut self) {
+        let _ = self.flush_buffer();
+    }
 }
 
-pub fn write_u16(file: &mut File, data: u16) -> Result<(), SnowBinError> {
-    let buffer = &(data).to_le_bytes();
-    error(file.by_ref().write_all(buffer))
+#[cfg(feature = "std")]
+impl Patch for IoOutput<File> {
+    fn patch(&mut self, offset: u64, data: &[u8]) -> Result<(), SnowBinError> {
+        let end = self.offset;
+        self.error(|w| {
+            w.seek(SeekFrom::Start(offset))?;
+            w.write_all(data)?;
+            w.seek(SeekFrom::Start(end))
+        })?;
+        Ok(())
+    }
+
+    fn read_back(&mut self, offset: u64, buffer: &mut [u8]) -> Result<(), SnowBinError> {
+        let end = self.offset;
+        self.error(|w| {
+            w.seek(SeekFrom::Start(offset))?;
+            w.read_exact(buffer)?;
+            w.seek(SeekFrom::Start(end))
+        })?;
+        Ok(())
+    }
+
+    fn truncate(&mut self, offset: u64) -> Result<(), SnowBinError> {
+        self.error(|w| {
+            w.set_len(offset)?;
+            w.seek(SeekFrom::Start(offset))
+        })?;
+        self.offset = offset;
+        Ok(())
+    }
+}
+
+/// Pads `header` with spaces to `header_len` bytes.
+pub fn encode_header(header: &str, header_len: u32) -> Result<Vec<u8>, SnowBinError> {
+    if header.len() > header_len as usize {
+        return Err(SnowBinError::HeaderTooLong);
+    }
+
+    let null_buffer = vec![32_u8; header_len as usize - header.len()];
+    Ok([header.as_bytes(), &null_buffer].concat())
 }
 
-pub fn write_u32(file: &mut File, data: u32) -> Result<(), SnowBinError> {
-    let buffer = &(data).to_le_bytes();
-    error(file.by_ref().write_all(buffer))
+/// Encodes `data` as a LEB128 varint, returning the buffer and how many bytes of it are used.
+pub const fn encode_varint(mut data: u64) -> ([u8; 10], usize) {
+    let mut buffer = [0_u8; 10];
+    let mut len = 0;
+    loop {
+        #[allow(clippy::cast_possible_truncation)]
+        let byte = (data & 0x7f) as u8;
+        data >>= 7;
+        if data == 0 {
+            buffer[len] = byte;
+            return (buffer, len + 1);
+        }
+        buffer[len] = byte | 0x80;
+        len += 1;
+    }
 }
 
-pub fn write_u64(file: &mut File, data: u64) -> Result<(), SnowBinError> {
-    let buffer = &(data).to_le_bytes();
-    error(file.by_ref().write_all(buffer))
+/// Pads an encoded varint to the full 10 bytes, using continuation bytes that add no value.
+pub const fn pad_varint(mut buffer: [u8; 10], len: usize) -> [u8; 10] {
+    let mut i = len;
+    while i < 10 {
+        buffer[i - 1] |= 0x80;
+        buffer[i] = 0;
+        i += 1;
+    }
+    buffer
 }